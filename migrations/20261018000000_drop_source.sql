@@ -0,0 +1,2 @@
+-- Record where a drop came from (NPC, raid, activity)
+ALTER TABLE drops ADD COLUMN source TEXT;
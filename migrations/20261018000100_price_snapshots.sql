@@ -0,0 +1,10 @@
+-- Hourly copies of the wiki /latest prices so drops can be valued at the time they happened
+CREATE TABLE IF NOT EXISTS price_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    high INTEGER,
+    low INTEGER,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_snapshots_item_time ON price_snapshots (item_id, timestamp);
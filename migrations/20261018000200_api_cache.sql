@@ -0,0 +1,6 @@
+-- Last good response from each external API, used when the API can't be reached
+CREATE TABLE IF NOT EXISTS api_cache (
+    name TEXT PRIMARY KEY,
+    body TEXT NOT NULL,
+    fetched_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,42 @@
+-- Point formula parameters, editable with /scoring_set. Missing rows fall back to these defaults in scoring.rs
+CREATE TABLE IF NOT EXISTS scoring_rules (
+    name TEXT PRIMARY KEY,
+    value REAL NOT NULL
+);
+
+INSERT OR IGNORE INTO scoring_rules (name, value) VALUES
+    ('gp_per_point', 100000),
+    ('rare_max_rate', 5),
+    ('rare_base', 100),
+    ('rare_multiplier', 30),
+    ('rare_exponent', 1.5),
+    ('uncommon_max_rate', 20),
+    ('uncommon_min_points', 200),
+    ('uncommon_max_points', 500),
+    ('common_base', 100),
+    ('common_slope', 0.5),
+    ('clamp_cap', 3000);
+
+-- Fixed points for specific items, per item for drops and per entry for clogs
+CREATE TABLE IF NOT EXISTS scoring_overrides (
+    kind TEXT NOT NULL CHECK (kind IN ('drop', 'clog')),
+    item_name TEXT NOT NULL,
+    points INTEGER NOT NULL,
+    PRIMARY KEY (kind, item_name)
+);
+
+-- Store the points each drop awarded so removing it takes back exactly that, whatever the rules are now
+ALTER TABLE drops ADD COLUMN points INTEGER NOT NULL DEFAULT 0;
+UPDATE drops SET points = value / 100000;
+
+DROP VIEW IF EXISTS v_users;
+CREATE VIEW IF NOT EXISTS v_users as 
+with droptable as (
+    select discord_id, sum(points) as drop_points, count(id) as drop_count from drops group by discord_id
+),
+clogtable as (
+    select discord_id, sum(points) as clog_points, count(item_name) as clog_count from collection_log_entries group by discord_id
+)
+select users.discord_id, drop_points, clog_points, COALESCE(drop_points,0) + COALESCE(clog_points,0) as total_points, drop_count, clog_count from users
+left join droptable on users.discord_id = droptable.discord_id
+left join clogtable on users.discord_id = clogtable.discord_id;
@@ -0,0 +1,46 @@
+-- Append-only record of every point change. users.points is kept equal to the sum of a member's deltas
+CREATE TABLE IF NOT EXISTS point_transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    delta INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    -- The drops/collection_log_entries row behind the change, if any (it may since have been deleted)
+    source_table TEXT,
+    source_id INTEGER,
+    -- Discord ID of whoever caused the change, NULL for automatic tracking
+    actor_id TEXT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_point_transactions_user ON point_transactions (discord_id, timestamp);
+
+-- Everything from before the ledger existed goes in as one opening balance per member
+INSERT INTO point_transactions (discord_id, delta, reason)
+SELECT discord_id, points, 'opening_balance' FROM users WHERE points != 0;
+
+CREATE TRIGGER IF NOT EXISTS point_transactions_no_update
+BEFORE UPDATE ON point_transactions
+BEGIN
+    SELECT RAISE(ABORT, 'point_transactions is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS point_transactions_no_delete
+BEFORE DELETE ON point_transactions
+BEGIN
+    SELECT RAISE(ABORT, 'point_transactions is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS point_transactions_apply
+AFTER INSERT ON point_transactions
+BEGIN
+    INSERT OR IGNORE INTO users (discord_id, points, total_drops) VALUES (NEW.discord_id, 0, 0);
+    UPDATE users SET points = points + NEW.delta WHERE discord_id = NEW.discord_id;
+END;
+
+-- Points can only move through the ledger
+CREATE TRIGGER IF NOT EXISTS users_points_from_ledger
+BEFORE UPDATE OF points ON users
+WHEN NEW.points != (SELECT COALESCE(SUM(delta), 0) FROM point_transactions WHERE discord_id = NEW.discord_id)
+BEGIN
+    SELECT RAISE(ABORT, 'users.points must match point_transactions, record a transaction instead');
+END;
@@ -0,0 +1,27 @@
+-- Each /recalculate run, so it can be reverted with /recalculate_undo
+CREATE TABLE IF NOT EXISTS recalculation_batches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor_id TEXT NOT NULL,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    -- Set once the batch has been undone, a batch can only be undone once
+    undone_at DATETIME,
+    undone_by TEXT
+);
+
+-- Every collection_log_entries points change a batch made
+CREATE TABLE IF NOT EXISTS recalculation_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    batch_id INTEGER NOT NULL REFERENCES recalculation_batches(id),
+    entry_id INTEGER NOT NULL,
+    discord_id TEXT NOT NULL,
+    old_points INTEGER NOT NULL,
+    new_points INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_recalculation_changes_batch ON recalculation_changes (batch_id);
+
+-- What each batch did to each member's total
+CREATE VIEW IF NOT EXISTS v_recalculation_deltas AS
+SELECT batch_id, discord_id, SUM(new_points - old_points) AS delta, COUNT(*) AS entries
+FROM recalculation_changes
+GROUP BY batch_id, discord_id;
@@ -0,0 +1,24 @@
+-- Per-category clamp caps, NULL means the clamp_cap scoring rule
+ALTER TABLE category_table ADD COLUMN clamp_cap INTEGER;
+
+-- v_item_data gains clamp_cap: the lowest cap of the item's clamped categories
+DROP VIEW IF EXISTS v_item_data;
+CREATE VIEW IF NOT EXISTS v_item_data AS WITH linkedcats as (
+                SELECT item_id, v_categories_clogs.category FROM v_categories_clogs
+            ),
+	clampedcats as (
+	SELECT linkedcats.item_id, group_concat(category_table.category, ', ') as clamped_category, clamp,
+	MIN(CAST(COALESCE(category_table.clamp_cap, (SELECT value FROM scoring_rules WHERE name = 'clamp_cap')) AS REAL)) as clamp_cap
+	FROM
+	category_table
+	INNER JOIN linkedcats ON linkedcats.category=category_table.category
+	WHERE clamp = 1
+	GROUP BY item_id),
+	clogtable as (
+    SELECT collection_log_entries.item_name as item_name, count(item_name) as clog_count, points from collection_log_entries where points > 0 group by item_name order by points ASC
+)
+SELECT collection_log_items.item_id as item_id, collection_log_items.item_name as item_name, preferred_name, categories, percentage, coalesce(points,0) as highest_points, whitelist, coalesce(clog_count,0) as clog_count, coalesce(clamp,0) as clamp, coalesce(clamped_category,' ') as clamped_category, clamp_cap
+FROM collection_log_items
+LEFT JOIN clampedcats ON clampedcats.item_id=collection_log_items.item_id
+LEFT JOIN clogtable ON clogtable.item_name=collection_log_items.item_name
+ORDER BY item_id;
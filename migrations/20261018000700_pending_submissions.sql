@@ -0,0 +1,25 @@
+-- Manual /drop and /clog submissions waiting for a mod when REQUIRE_APPROVAL is on.
+-- Points are only awarded, through the usual drops/collection_log_entries rows, once approved
+CREATE TABLE IF NOT EXISTS pending_submissions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL CHECK (kind IN ('drop', 'clog')),
+    discord_id TEXT NOT NULL,
+    -- Display name at submission time, used for rank up messages
+    user_name TEXT NOT NULL,
+    item_name TEXT NOT NULL,
+    quantity INTEGER NOT NULL DEFAULT 1,
+    -- Total value of a drop at the price it was submitted with
+    value INTEGER,
+    source TEXT,
+    -- When the drop happened if it was backdated with `when`
+    dropped_at DATETIME,
+    screenshot_url TEXT NOT NULL,
+    -- Where it was submitted, so the outcome can be posted there
+    channel_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'denied')),
+    reviewer_id TEXT,
+    reviewed_at DATETIME,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_pending_submissions_status ON pending_submissions (status, discord_id);
@@ -0,0 +1,7 @@
+-- Screenshot evidence for drops and collection log entries: the Discord attachment URL and,
+-- when SCREENSHOT_DIR is set, where the downloaded copy was saved
+ALTER TABLE drops ADD COLUMN screenshot_url TEXT;
+ALTER TABLE drops ADD COLUMN screenshot_path TEXT;
+ALTER TABLE collection_log_entries ADD COLUMN screenshot_url TEXT;
+ALTER TABLE collection_log_entries ADD COLUMN screenshot_path TEXT;
+ALTER TABLE pending_submissions ADD COLUMN screenshot_path TEXT;
@@ -0,0 +1,12 @@
+-- Points given or taken by an admin with /points_adjust, e.g. event prizes and penalties.
+-- The ledger entry for each one points back here with source_table = 'point_adjustments'
+CREATE TABLE IF NOT EXISTS point_adjustments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    delta INTEGER NOT NULL CHECK (delta != 0),
+    reason TEXT NOT NULL,
+    actor_id TEXT NOT NULL,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_point_adjustments_user ON point_adjustments (discord_id, timestamp);
@@ -0,0 +1,26 @@
+-- Group drops (raids, group bosses) split between teammates. Each participant gets their own
+-- drops row holding their share of the value and points, linked to the split by split_id
+CREATE TABLE IF NOT EXISTS drop_splits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_name TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    -- Value and points of the whole drop, divided between the participants by their share
+    total_value INTEGER NOT NULL,
+    total_points INTEGER NOT NULL,
+    source TEXT,
+    -- Discord ID of whoever split it
+    actor_id TEXT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE drops ADD COLUMN split_id INTEGER REFERENCES drop_splits(id);
+-- The participant's weight in the split, e.g. 2 and 1 for a two-thirds/one-third split
+ALTER TABLE drops ADD COLUMN share INTEGER;
+-- The RuneLite message an auto-tracked drop came from, so teammates can react to join it
+ALTER TABLE drops ADD COLUMN message_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_drops_split ON drops (split_id);
+CREATE INDEX IF NOT EXISTS idx_drops_message ON drops (message_id);
+
+-- Teammates of a split submission as `discord_id:share` pairs, the submitter first
+ALTER TABLE pending_submissions ADD COLUMN shares TEXT;
@@ -0,0 +1,59 @@
+-- Pets from /pet and the RuneLite channel. The plain text game messages don't say which pet it was,
+-- so pet_name is only known for Dink notifications and manual entries
+CREATE TABLE IF NOT EXISTS pets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    pet_name TEXT,
+    -- "You have a funny feeling like you would have been followed", a pet the member already had
+    duplicate INTEGER NOT NULL DEFAULT 0,
+    points INTEGER NOT NULL DEFAULT 0,
+    screenshot_url TEXT,
+    screenshot_path TEXT,
+    -- The RuneLite message an auto-tracked pet came from
+    message_id TEXT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_pets_discord_id ON pets (discord_id, pet_name);
+
+INSERT OR IGNORE INTO scoring_rules (name, value) VALUES
+    ('pet_points', 500),
+    ('duplicate_pet_points', 0);
+
+-- SQLite can't alter a CHECK constraint, so the submissions table is rebuilt to allow pets
+CREATE TABLE pending_submissions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL CHECK (kind IN ('drop', 'clog', 'pet')),
+    discord_id TEXT NOT NULL,
+    -- Display name at submission time, used for rank up messages
+    user_name TEXT NOT NULL,
+    item_name TEXT NOT NULL,
+    quantity INTEGER NOT NULL DEFAULT 1,
+    -- Total value of a drop at the price it was submitted with
+    value INTEGER,
+    source TEXT,
+    -- When the drop happened if it was backdated with `when`
+    dropped_at DATETIME,
+    screenshot_url TEXT NOT NULL,
+    -- Where it was submitted, so the outcome can be posted there
+    channel_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'denied')),
+    reviewer_id TEXT,
+    reviewed_at DATETIME,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    screenshot_path TEXT,
+    -- Teammates of a split submission as `discord_id:share` pairs, the submitter first
+    shares TEXT
+);
+
+INSERT INTO pending_submissions_new
+    (id, kind, discord_id, user_name, item_name, quantity, value, source, dropped_at, screenshot_url,
+     channel_id, status, reviewer_id, reviewed_at, timestamp, screenshot_path, shares)
+SELECT id, kind, discord_id, user_name, item_name, quantity, value, source, dropped_at, screenshot_url,
+       channel_id, status, reviewer_id, reviewed_at, timestamp, screenshot_path, shares
+FROM pending_submissions;
+
+DROP TABLE pending_submissions;
+ALTER TABLE pending_submissions_new RENAME TO pending_submissions;
+
+CREATE INDEX IF NOT EXISTS idx_pending_submissions_status ON pending_submissions (status, discord_id);
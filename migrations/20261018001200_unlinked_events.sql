@@ -0,0 +1,27 @@
+-- Drops, clogs and pets from the RuneLite channel for an RS name nobody has linked yet. They wait here
+-- until someone links the name with /rsname and confirms they're theirs, or a mod dismisses them
+CREATE TABLE IF NOT EXISTS unlinked_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rs_name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('drop', 'clog', 'pet')),
+    -- NULL for a pet the notification didn't name
+    item_name TEXT,
+    item_id INTEGER,
+    quantity INTEGER NOT NULL DEFAULT 1,
+    -- Total value of a drop in gp
+    value INTEGER,
+    source TEXT,
+    duplicate INTEGER NOT NULL DEFAULT 0,
+    screenshot_url TEXT,
+    screenshot_path TEXT,
+    message_id TEXT,
+    -- Jump link to the RuneLite message, so mods can check it
+    message_link TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'credited', 'dismissed')),
+    -- Who it was credited to, or the mod who dismissed it
+    resolved_by TEXT,
+    resolved_at DATETIME,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_unlinked_events_pending ON unlinked_events (status, rs_name COLLATE NOCASE);
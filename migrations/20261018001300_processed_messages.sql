@@ -0,0 +1,12 @@
+-- RuneLite channel messages the tracker has already been through, so /backfill and the startup
+-- catch-up skip them
+CREATE TABLE IF NOT EXISTS processed_messages (
+    message_id TEXT PRIMARY KEY,
+    processed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Messages that already produced rows before this table existed
+INSERT OR IGNORE INTO processed_messages (message_id)
+SELECT message_id FROM drops WHERE message_id IS NOT NULL
+UNION SELECT message_id FROM pets WHERE message_id IS NOT NULL
+UNION SELECT message_id FROM unlinked_events WHERE message_id IS NOT NULL;
@@ -0,0 +1,21 @@
+-- What each RuneLite message produced, so the same message can never award points twice and mods can
+-- see what came of it with /message_lookup
+ALTER TABLE processed_messages ADD COLUMN status TEXT NOT NULL DEFAULT 'done' CHECK (status IN ('processing', 'done', 'failed'));
+-- Parser that recognised the message and the events it found as JSON, both NULL if nothing did
+ALTER TABLE processed_messages ADD COLUMN parser TEXT;
+ALTER TABLE processed_messages ADD COLUMN events TEXT;
+
+-- Rows created from a message: drops, collection_log_entries, pets and unlinked_events
+CREATE TABLE IF NOT EXISTS processed_message_rows (
+    message_id TEXT NOT NULL REFERENCES processed_messages(message_id),
+    source_table TEXT NOT NULL,
+    source_id INTEGER NOT NULL,
+    PRIMARY KEY (source_table, source_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_processed_message_rows_message ON processed_message_rows (message_id);
+
+INSERT OR IGNORE INTO processed_message_rows (message_id, source_table, source_id)
+SELECT message_id, 'drops', id FROM drops WHERE message_id IS NOT NULL
+UNION ALL SELECT message_id, 'pets', id FROM pets WHERE message_id IS NOT NULL
+UNION ALL SELECT message_id, 'unlinked_events', id FROM unlinked_events WHERE message_id IS NOT NULL;
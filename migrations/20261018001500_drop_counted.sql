@@ -0,0 +1,16 @@
+-- Whether the drop counts towards the member's total_drops. A split drop is still one drop, counted for
+-- the member who got it; the rows holding their teammates' shares aren't counted again
+ALTER TABLE drops ADD COLUMN counted INTEGER NOT NULL DEFAULT 1;
+
+-- Until now every share added the whole quantity to its participant's total_drops. The first share of
+-- a split is always the original recipient's, the rest are teammates'
+UPDATE users SET total_drops = total_drops - (
+    SELECT COALESCE(SUM(d.quantity), 0) FROM drops d
+    WHERE d.discord_id = users.discord_id
+      AND d.split_id IS NOT NULL
+      AND d.id != (SELECT MIN(first.id) FROM drops first WHERE first.split_id = d.split_id)
+);
+
+UPDATE drops SET counted = 0
+WHERE split_id IS NOT NULL
+  AND id != (SELECT MIN(first.id) FROM drops first WHERE first.split_id = drops.split_id);
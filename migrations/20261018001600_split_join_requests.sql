@@ -0,0 +1,16 @@
+-- Teammates asking to join an auto-tracked drop with 🤝. They only get a share once one of the drop's
+-- recipients, or a mod, confirms it
+CREATE TABLE IF NOT EXISTS split_join_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- The RuneLite message the drop came from
+    message_id TEXT NOT NULL,
+    joiner_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reviewer_id TEXT,
+    reviewed_at DATETIME,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (message_id, joiner_id)
+);
+
+-- Bumped every time a split is re-divided, so two joins can't both rewrite the shares from the same state
+ALTER TABLE drop_splits ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
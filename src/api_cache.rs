@@ -0,0 +1,67 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::de::DeserializeOwned;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+pub struct Fetched<T> {
+    pub data: T,
+    /// True when the API couldn't be reached and `data` is the last cached copy
+    pub from_cache: bool,
+}
+
+/// Fetches `url` and parses it as JSON, keeping a copy of the body in `api_cache` under `name`.
+/// If the request or parsing fails, the last cached copy is returned instead.
+pub async fn fetch_json<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    db: &SqlitePool,
+    name: &str,
+    url: &str,
+) -> Result<Fetched<T>> {
+    let fetched = async {
+        let body = client.get(url).send().await?.error_for_status()?.text().await?;
+        let parsed = serde_json::from_str::<T>(&body)?;
+        anyhow::Ok((body, parsed))
+    }
+    .await;
+
+    match fetched {
+        Ok((body, parsed)) => {
+            if let Err(e) = store(db, name, &body).await {
+                warn!("Failed to cache {} response: {}", name, e);
+            }
+            Ok(Fetched { data: parsed, from_cache: false })
+        }
+        Err(e) => {
+            warn!("Failed to fetch {} from {}: {}, trying the cached copy", name, url, e);
+            let Some((body, fetched_at)) = load(db, name).await? else {
+                return Err(e.context(format!("No cached copy of {} either", name)));
+            };
+            info!("Using cached {} from {}", name, fetched_at);
+            Ok(Fetched { data: serde_json::from_str(&body)?, from_cache: true })
+        }
+    }
+}
+
+pub async fn store(db: &SqlitePool, name: &str, body: &str) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO api_cache (name, body, fetched_at) VALUES (?, ?, CURRENT_TIMESTAMP)
+         ON CONFLICT(name) DO UPDATE SET body = excluded.body, fetched_at = excluded.fetched_at",
+        name,
+        body
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// The cached body and when it was fetched (UTC)
+pub async fn load(db: &SqlitePool, name: &str) -> Result<Option<(String, NaiveDateTime)>> {
+    let row = sqlx::query!(
+        r#"SELECT body, fetched_at AS "fetched_at!: NaiveDateTime" FROM api_cache WHERE name = ?"#,
+        name
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|row| (row.body, row.fetched_at)))
+}
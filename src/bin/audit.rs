@@ -0,0 +1,289 @@
+//! Checks the stored counters against the rows they're built from and reports anything that doesn't add up:
+//!
+//! - `users.points` that doesn't match the member's `point_transactions`
+//! - ledger totals that don't match the points on their drops, collection log entries, pets and adjustments.
+//!   The pre-ledger opening balance also holds recalculated and clamped points the rows can't rebuild,
+//!   so members with one usually show up here; it's reported for a mod to judge, never fixed
+//! - `users.total_drops` that doesn't match their drops (a split counts once, for its original recipient)
+//! - rows belonging to a Discord ID with no `users` row
+//! - collection log entries that don't match any `collection_log_items` item
+//!
+//! Run with `--fix` to repair the counters in a single transaction. `users.points` is reset to the
+//! ledger total, the ledger itself is never changed.
+
+use anyhow::Result;
+use dotenvy::dotenv;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use std::env;
+use tracing::info;
+
+struct UserDrift {
+    discord_id: String,
+    points: i64,
+    ledger_points: i64,
+    /// The part of `ledger_points` carried over from before the ledger existed
+    opening_balance: i64,
+    row_points: i64,
+    total_drops: i64,
+    drop_quantity: i64,
+}
+
+struct Orphan {
+    table: String,
+    discord_id: String,
+    rows: i64,
+}
+
+struct UnknownClog {
+    item_name: String,
+    item_id: Option<i64>,
+    entries: i64,
+    /// An item with the same name exists, the entry just never had its ID filled in
+    fixable: bool,
+}
+
+async fn find_user_drift(db: &SqlitePool) -> Result<Vec<UserDrift>> {
+    let rows = sqlx::query!(
+        r#"WITH ledger AS (
+            SELECT discord_id,
+                   SUM(delta) AS points,
+                   SUM(CASE WHEN reason = 'opening_balance' THEN delta ELSE 0 END) AS opening_balance
+            FROM point_transactions
+            GROUP BY discord_id
+        ),
+        drop_totals AS (
+            SELECT discord_id, SUM(points) AS points, SUM(CASE WHEN counted THEN quantity ELSE 0 END) AS quantity
+            FROM drops
+            GROUP BY discord_id
+        ),
+        clog_totals AS (
+            SELECT discord_id, SUM(points) AS points FROM collection_log_entries GROUP BY discord_id
+        ),
+        pet_totals AS (
+            SELECT discord_id, SUM(points) AS points FROM pets GROUP BY discord_id
+        ),
+        adjustment_totals AS (
+            SELECT discord_id, SUM(delta) AS points FROM point_adjustments GROUP BY discord_id
+        ),
+        totals AS (
+            SELECT u.discord_id,
+                   u.points,
+                   u.total_drops,
+                   COALESCE(l.points, 0) AS ledger_points,
+                   COALESCE(l.opening_balance, 0) AS opening_balance,
+                   COALESCE(d.points, 0) + COALESCE(c.points, 0) + COALESCE(p.points, 0) + COALESCE(a.points, 0) AS row_points,
+                   COALESCE(d.quantity, 0) AS drop_quantity
+            FROM users u
+            LEFT JOIN ledger l ON l.discord_id = u.discord_id
+            LEFT JOIN drop_totals d ON d.discord_id = u.discord_id
+            LEFT JOIN clog_totals c ON c.discord_id = u.discord_id
+            LEFT JOIN pet_totals p ON p.discord_id = u.discord_id
+            LEFT JOIN adjustment_totals a ON a.discord_id = u.discord_id
+        )
+        SELECT discord_id AS "discord_id!: String",
+               points AS "points!: i64",
+               ledger_points AS "ledger_points!: i64",
+               opening_balance AS "opening_balance!: i64",
+               row_points AS "row_points!: i64",
+               total_drops AS "total_drops!: i64",
+               drop_quantity AS "drop_quantity!: i64"
+        FROM totals
+        WHERE points != ledger_points OR ledger_points != row_points OR total_drops != drop_quantity
+        ORDER BY discord_id"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| UserDrift {
+            discord_id: row.discord_id,
+            points: row.points,
+            ledger_points: row.ledger_points,
+            opening_balance: row.opening_balance,
+            row_points: row.row_points,
+            total_drops: row.total_drops,
+            drop_quantity: row.drop_quantity,
+        })
+        .collect())
+}
+
+async fn find_orphans(db: &SqlitePool) -> Result<Vec<Orphan>> {
+    let rows = sqlx::query!(
+        r#"SELECT source AS "source!: String", discord_id AS "discord_id!: String", COUNT(*) AS "rows!: i64" FROM (
+            SELECT 'drops' AS source, discord_id FROM drops
+            UNION ALL SELECT 'collection_log_entries', discord_id FROM collection_log_entries
+            UNION ALL SELECT 'pets', discord_id FROM pets
+            UNION ALL SELECT 'runescape_accounts', discord_id FROM runescape_accounts
+            UNION ALL SELECT 'point_transactions', discord_id FROM point_transactions
+            UNION ALL SELECT 'point_adjustments', discord_id FROM point_adjustments
+        )
+        WHERE discord_id NOT IN (SELECT discord_id FROM users WHERE discord_id IS NOT NULL)
+        GROUP BY source, discord_id
+        ORDER BY discord_id, source"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Orphan {
+            table: row.source,
+            discord_id: row.discord_id,
+            rows: row.rows,
+        })
+        .collect())
+}
+
+async fn find_unknown_clogs(db: &SqlitePool) -> Result<Vec<UnknownClog>> {
+    let rows = sqlx::query!(
+        r#"SELECT e.item_name AS "item_name!: String",
+                  e.item_id,
+                  COUNT(*) AS "entries!: i64",
+                  EXISTS (SELECT 1 FROM collection_log_items i WHERE i.item_name = e.item_name) AS "fixable!: bool"
+        FROM collection_log_entries e
+        WHERE e.item_id IS NULL
+           OR e.item_id NOT IN (SELECT item_id FROM collection_log_items)
+        GROUP BY e.item_name, e.item_id
+        ORDER BY e.item_name"#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| UnknownClog {
+            item_name: row.item_name,
+            item_id: row.item_id,
+            entries: row.entries,
+            fixable: row.fixable,
+        })
+        .collect())
+}
+
+async fn fix(db: &SqlitePool) -> Result<()> {
+    let mut tx = db.begin().await?;
+
+    // Give every orphaned Discord ID a users row, the counters are filled in below
+    let created = sqlx::query!(
+        "INSERT OR IGNORE INTO users (discord_id, points, total_drops)
+         SELECT discord_id, 0, 0 FROM drops
+         UNION SELECT discord_id, 0, 0 FROM collection_log_entries
+         UNION SELECT discord_id, 0, 0 FROM pets
+         UNION SELECT discord_id, 0, 0 FROM runescape_accounts
+         UNION SELECT discord_id, 0, 0 FROM point_transactions
+         UNION SELECT discord_id, 0, 0 FROM point_adjustments"
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    info!("Created {} missing users", created);
+
+    // Counters that were changed outside the ledger. The ledger is the source of truth, the rows can't
+    // rebuild balances from before it (recalculations, clamps), so ledger vs rows drift is only reported
+    let points_fixed = sqlx::query!(
+        "UPDATE users
+         SET points = (SELECT COALESCE(SUM(delta), 0) FROM point_transactions t WHERE t.discord_id = users.discord_id)
+         WHERE points != (SELECT COALESCE(SUM(delta), 0) FROM point_transactions t WHERE t.discord_id = users.discord_id)"
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    info!("Reset points for {} users", points_fixed);
+
+    let drops_fixed = sqlx::query!(
+        "UPDATE users
+         SET total_drops = (SELECT COALESCE(SUM(quantity), 0) FROM drops d WHERE d.discord_id = users.discord_id AND d.counted)
+         WHERE total_drops != (SELECT COALESCE(SUM(quantity), 0) FROM drops d WHERE d.discord_id = users.discord_id AND d.counted)"
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    info!("Reset total drops for {} users", drops_fixed);
+
+    let clogs_fixed = sqlx::query!(
+        "UPDATE collection_log_entries
+         SET item_id = (SELECT item_id FROM collection_log_items i WHERE i.item_name = collection_log_entries.item_name)
+         WHERE (item_id IS NULL OR item_id NOT IN (SELECT item_id FROM collection_log_items))
+           AND EXISTS (SELECT 1 FROM collection_log_items i WHERE i.item_name = collection_log_entries.item_name)"
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    info!("Linked {} collection log entries to their items", clogs_fixed);
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Initialize environment variables
+    dotenv()?;
+
+    // Initialize logging
+    tracing_subscriber::fmt::init();
+
+    let apply_fixes = env::args().skip(1).any(|arg| arg == "--fix");
+
+    // Get database URL
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+    // Create database connection pool
+    let db = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+
+    let drift = find_user_drift(&db).await?;
+    let orphans = find_orphans(&db).await?;
+    let unknown_clogs = find_unknown_clogs(&db).await?;
+
+    println!("== User counters ({} with discrepancies) ==", drift.len());
+    for user in &drift {
+        let mut problems = Vec::new();
+        if user.points != user.ledger_points {
+            problems.push(format!("points {} but ledger says {}", user.points, user.ledger_points));
+        }
+        if user.ledger_points != user.row_points {
+            let mut problem = format!("ledger {} but drops/clogs/pets/adjustments add up to {}", user.ledger_points, user.row_points);
+            if user.opening_balance != 0 {
+                problem += format!(" ({} of the ledger is the pre-ledger opening balance)", user.opening_balance).as_str();
+            }
+            problems.push(problem);
+        }
+        if user.total_drops != user.drop_quantity {
+            problems.push(format!("total_drops {} but drops add up to {}", user.total_drops, user.drop_quantity));
+        }
+        println!("{}: {}", user.discord_id, problems.join("; "));
+    }
+
+    println!("\n== Rows without a user ({}) ==", orphans.len());
+    for orphan in &orphans {
+        println!("{}: {} rows in {}", orphan.discord_id, orphan.rows, orphan.table);
+    }
+
+    println!("\n== Collection log entries with no matching item ({}) ==", unknown_clogs.len());
+    for clog in &unknown_clogs {
+        println!(
+            "{} (item ID {}): {} entries{}",
+            clog.item_name,
+            clog.item_id.map_or("missing".to_string(), |id| id.to_string()),
+            clog.entries,
+            if clog.fixable { ", can be linked by name" } else { "" }
+        );
+    }
+
+    let problems = drift.len() + orphans.len() + unknown_clogs.len();
+    if problems == 0 {
+        println!("\nEverything adds up!");
+    } else if apply_fixes {
+        fix(&db).await?;
+        println!("\nFixes applied. Ledger vs rows differences and collection log entries that can't be linked by name need checking by hand.");
+    } else {
+        println!("\nRun with --fix to repair the counters.");
+    }
+
+    Ok(())
+}
@@ -3,7 +3,6 @@ use dotenvy::dotenv;
 use sqlx::sqlite::SqlitePoolOptions;
 use std::env;
 use tracing::info;
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<()> {
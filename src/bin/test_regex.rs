@@ -1,40 +1,58 @@
-use regex::Regex;
+use kittyscape_loot_bot::parsers::ParserRegistry;
+use serenity::all::{Embed, Message};
+
+// Builds a message with an embed the way the Rare Drop Notificater plugin posts it
+fn embed_message(author: &str, description: &str) -> Message {
+    let embed: Embed = serde_json::from_value(serde_json::json!({
+        "author": { "name": author },
+        "description": description,
+    }))
+    .expect("Invalid embed");
+
+    let mut msg = Message::default();
+    msg.embeds = vec![embed];
+    msg
+}
+
+fn text_message(content: &str) -> Message {
+    let mut msg = Message::default();
+    msg.content = content.to_string();
+    msg
+}
+
+#[tokio::main]
+async fn main() {
+    // Same parsers the bot uses
+    let registry = ParserRegistry::default();
 
-fn main() {
-    // Define the regex pattern we want to test
-    let pattern = r"Just got (?:(\d+)x\s+)?\[(.+?)(?:\]\(.+?\)|\])\s+from(?:\s+lvl\s+\d+)?\s+\[(.+?)(?:\]\(.+?\)|\])";
-    let re = Regex::new(pattern).unwrap();
-    
     // Test cases
-    let test_cases = vec![
-        "Just got [Coal] from [Monster]",
-        "Just got 5x [Coal] from [Monster]",
-        "Just got [Coal](https://oldschool.runescape.wiki/w/Special:Search?search=Coal) from [Monster](https://oldschool.runescape.wiki/w/Special:Search?search=Monster)",
-        "Just got 5x [Coal](https://oldschool.runescape.wiki/w/Special:Search?search=Coal) from lvl 98 [Sulphur Nagua](https://oldschool.runescape.wiki/w/Special:Search?search=Sulphur%20Nagua)",
+    let test_cases = [
+        embed_message("Test Player", "Just got [Coal] from [Monster]"),
+        embed_message("Test Player", "Just got 5x [Coal] from [Monster]"),
+        embed_message("Test Player", "Just got [Coal](https://oldschool.runescape.wiki/w/Special:Search?search=Coal) from [Monster](https://oldschool.runescape.wiki/w/Special:Search?search=Monster)"),
+        embed_message("Test Player", "Just got 5x [Coal](https://oldschool.runescape.wiki/w/Special:Search?search=Coal) from lvl 98 [Sulphur Nagua](https://oldschool.runescape.wiki/w/Special:Search?search=Sulphur%20Nagua)"),
+        text_message("Test Player received: Dragon bones (3x) (8,100 coins)"),
+        text_message("**Test Player** New item added to your collection log: **Dragon warhammer**"),
+        text_message(r#"{"type":"LOOT","playerName":"Test Player","extra":{"items":[{"id":11832,"quantity":1,"priceEach":12000000,"name":"Bandos chestplate"}],"source":"General Graardor"}}"#),
     ];
-    
+
     // Test each case
     for (i, test_case) in test_cases.iter().enumerate() {
-        println!("Test Case #{}: {}", i + 1, test_case);
-        
-        if let Some(captures) = re.captures(test_case) {
-            println!("  ✅ Matched!");
-            
-            // Extract quantity (or default to 1)
-            let quantity = captures.get(1).map_or("1", |m| m.as_str());
-            println!("  Quantity: {}", quantity);
-            
-            // Extract item name
-            let item_name = captures.get(2).map_or("", |m| m.as_str());
-            println!("  Item: {}", item_name);
-            
-            // Extract source/monster
-            let source = captures.get(3).map_or("", |m| m.as_str());
-            println!("  Source: {}", source);
-        } else {
-            println!("  ❌ No match!");
+        let text = test_case.embeds.first()
+            .and_then(|embed| embed.description.as_deref())
+            .unwrap_or(&test_case.content);
+        println!("Test Case #{}: {}", i + 1, text);
+
+        match registry.parse(test_case).await {
+            Some((parser, events)) => {
+                println!("  ✅ Matched by {} parser!", parser);
+                for event in events {
+                    println!("  {:?}", event);
+                }
+            }
+            None => println!("  ❌ No match!"),
         }
-        
-        println!("");
+
+        println!();
     }
-} 
\ No newline at end of file
+}
@@ -1,16 +1,18 @@
 use anyhow::Result;
-use scraper::ElementRef;
-use serenity::futures::{AsyncReadExt, TryStreamExt};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use serde_json::Value;
 use html_escape::decode_html_entities;
-use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool, query};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use crate::scoring::ScoringRules;
 
 const USER_AGENT: &str = "KittyScape Loot Bot/1.0";
-const WIKI_API_URL: &str = "https://oldschool.runescape.wiki/api.php";
+/// Completion rates drift slowly, so once a day is plenty
+const REFRESH_INTERVAL_SECS: u64 = 24 * 60 * 60;
+/// The collection log category that lists every pet, used for /pet autocomplete
+const PET_CATEGORY: &str = "All Pets";
 
 #[derive(Debug, Clone)]
 pub struct CollectionLogData {
@@ -18,7 +20,7 @@ pub struct CollectionLogData {
 }
 
 pub struct CollectionLogItem {
-    pub item_id: f64,
+    pub item_id: i64,
     pub item_name: String,
     pub preferred_name: String,
     pub percentage: f64,
@@ -30,29 +32,72 @@ pub struct CollectionLogItem {
 pub struct CollectionLogManager<> {
     data: Arc<RwLock<CollectionLogData>>,
     db: SqlitePool,
+    client: reqwest::Client,
+    wiki_api_url: String,
 }
 
 impl CollectionLogManager<> {
-    pub async fn new(db: &SqlitePool) -> Result<Self> {
+    /// Refreshes `collection_log_items` from the wiki, carrying on with whatever is already
+    /// in the table if the wiki can't be reached or parsed
+    pub async fn new(db: &SqlitePool, wiki_api_url: &str) -> Result<Self> {
         let client = reqwest::Client::builder()
             .user_agent(USER_AGENT)
             .build()?;
 
-        let completion_items: u64 = Self::fetch_completion_rates(&client, &db).await?;
-        info!("CollectionLogManager initialized with {} items", completion_items);
+        let manager = Self {
+            data: Arc::new(RwLock::new(CollectionLogData {
+                completion_rates: HashMap::new(),
+            })),
+            db: db.clone(),
+            client,
+            wiki_api_url: wiki_api_url.to_string(),
+        };
+
+        if let Err(e) = manager.refresh().await {
+            error!("Failed to refresh collection log data from the wiki, using the stored items: {}", e);
+            manager.load_completion_rates().await?;
+        }
+
+        info!("CollectionLogManager initialized with {} items", manager.data.read().await.completion_rates.len());
+        Ok(manager)
+    }
+
+    /// Re-scrapes the wiki table and reloads the completion rates from the database
+    pub async fn refresh(&self) -> Result<()> {
+        Self::fetch_completion_rates(&self.client, &self.db, &self.wiki_api_url).await?;
+        self.load_completion_rates().await
+    }
+
+    pub async fn start_updates(self: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(REFRESH_INTERVAL_SECS)).await;
+                if let Err(e) = self.refresh().await {
+                    error!("Failed to refresh collection log data: {}", e);
+                }
+            }
+        });
+    }
 
+    async fn load_completion_rates(&self) -> Result<()> {
         let completion_data = sqlx::query!(
             "SELECT item_name, percentage FROM collection_log_items",
         )
-        .fetch_all(db)
+        .fetch_all(&self.db)
         .await?;
 
         let mut completion_rates: HashMap<String, f64> = HashMap::new();
 
-        for comp_data_item in completion_data.iter() {
-            let item_name = comp_data_item.item_name.clone();
-            let percentage = comp_data_item.percentage.clone();
-            completion_rates.insert(item_name.unwrap(), percentage.unwrap().parse::<f64>().unwrap());
+        for comp_data_item in completion_data {
+            let (Some(item_name), Some(percentage)) = (comp_data_item.item_name, comp_data_item.percentage) else {
+                continue;
+            };
+            match percentage.parse::<f64>() {
+                Ok(rate) => {
+                    completion_rates.insert(item_name, rate);
+                }
+                Err(_) => warn!("Skipping {} with unparseable completion rate '{}'", item_name, percentage),
+            }
         }
 
         // Debug log some example items
@@ -60,17 +105,11 @@ impl CollectionLogManager<> {
             debug!("Example collection log item: {} - {}%", name, rate);
         }
 
-        let data = CollectionLogData {
-            completion_rates,
-        };
-
-        Ok(Self {
-            data: Arc::new(RwLock::new(data)),
-            db: db.clone(),
-        })
+        self.data.write().await.completion_rates = completion_rates;
+        Ok(())
     }
 
-    async fn fetch_completion_rates(client: &reqwest::Client, db: &SqlitePool) -> Result<u64> {
+    async fn fetch_completion_rates(client: &reqwest::Client, db: &SqlitePool, wiki_api_url: &str) -> Result<u64> {
         let mut items: Vec<CollectionLogItem> = Vec::new();
         
         
@@ -90,10 +129,11 @@ impl CollectionLogManager<> {
 
         info!("Fetching collection log data from wiki API...");
         let response = client
-            .get(WIKI_API_URL)
+            .get(wiki_api_url)
             .query(&table_params)
             .send()
-            .await?;
+            .await?
+            .error_for_status()?;
         
         info!("Got response with status: {}", response.status());
         let response_text = response.text().await?;
@@ -101,12 +141,6 @@ impl CollectionLogManager<> {
         
         let json: Value = serde_json::from_str(&response_text)?;
 
-        let mut data_insert: QueryBuilder<Sqlite> = QueryBuilder::new(
-            "INSERT INTO collection_log_items (item_id, item_name, preferred_name, percentage, categories) VALUES "
-        );
-
-        let mut data_insert_separated = data_insert.separated(", ");
-        
         if let Some(html) = json.get("parse")
             .and_then(|p| p.get("text"))
             .and_then(|t| t.get("*"))
@@ -142,6 +176,8 @@ impl CollectionLogManager<> {
             // </tr>
             let document = scraper::Html::parse_document(html);
             let selector = scraper::Selector::parse("tr[data-item-id]").unwrap();
+            let cell_selector = scraper::Selector::parse("td").unwrap();
+            let link_selector = scraper::Selector::parse("a").unwrap();
             let rows: Vec<_> = document.select(&selector).collect();
             debug!("Found {} table rows", rows.len());
 
@@ -149,51 +185,31 @@ impl CollectionLogManager<> {
                 debug!("Processing row {}", i);
 
                 debug!("{:#?}", row.value());
-                let item_id = row.value().attr("data-item-id").unwrap().parse::<f64>().unwrap();
+                let Some(item_id) = row.value().attr("data-item-id").and_then(|id| id.parse::<i64>().ok()) else {
+                    info!("Failed to parse item ID in row {}", i);
+                    continue;
+                };
                 
                 // Log the raw HTML of the row for debugging
                 debug!("Row HTML: {}", row.html());
                 
-                let cells: Vec<_> = row.select(&scraper::Selector::parse("td").unwrap()).collect();
+                let cells: Vec<_> = row.select(&cell_selector).collect();
                 debug!("Found {} cells in row", cells.len());
-                
+
+                // Skip the image link (first link) and get the item name link (second link)
+                let name_link = cells.first().and_then(|td| td.select(&link_selector).nth(1));
                 if let Some(first_cell) = cells.first() {
                     debug!("First cell HTML: {}", first_cell.html());
-                    
-                    let links: Vec<_> = first_cell.select(&scraper::Selector::parse("a").unwrap()).collect();
-                    debug!("Found {} links in first cell", links.len());
-                    
-                    if let Some(first_link) = links.first() {
-                        debug!("First link HTML: {}", first_link.html());
-                        debug!("First link attributes: {:?}", first_link.value().attrs);
-                    }
                 }
                 
-                if let Some(name) = row
-                    .select(&scraper::Selector::parse("td").unwrap())
-                    .next()
-                    .and_then(|td| {
-                        // Get all links in the cell
-                        let links: Vec<_> = td.select(&scraper::Selector::parse("a").unwrap()).collect();
-                        // Skip the image link (first link) and get the item name link (second link)
-                        links.get(1)
-                            .and_then(|a| a.value().attr("title"))
-                            .map(|s| decode_html_entities(s).into_owned())
-                    })
-                {
+                if let Some((name, name_link)) = name_link.and_then(|a| {
+                    a.value().attr("title").map(|s| (decode_html_entities(s).into_owned(), a))
+                }) {
                     info!("Found item name: {}", name);
 
-                    let preferred_name = row
-                    .select(&scraper::Selector::parse("td").unwrap())
-                    .next().unwrap()
-                    .select(&scraper::Selector::parse("a").unwrap())
-                    .collect::<Vec<ElementRef>>()
-                    .get(1).unwrap()
-                    .text()
-                    .collect::<String>();
+                    let preferred_name = name_link.text().collect::<String>();
                     
-                    if let Some(rate) = row
-                        .select(&scraper::Selector::parse("td").unwrap())
+                    if let Some(rate) = cells
                         .last()
                         .and_then(|td| td.text().next())
                         .map(|s| s.trim())
@@ -210,16 +226,13 @@ impl CollectionLogManager<> {
                             }
                         })
                     {
-                        if let Some(categories) = Some(row
-                            .select(&scraper::Selector::parse("td").unwrap())
-                            .nth(1)
-                            .unwrap()
-                            .text()
-                            .collect::<String>())
+                        if let Some(categories) = cells
+                            .get(1)
+                            .map(|td| td.text().collect::<String>())
                             {
                                 if !name.is_empty() {
                                     debug!("Found item: {} with rate: {}% and categories: {}", name, rate, categories);
-                                    let item = CollectionLogItem{item_id: item_id,
+                                    let item = CollectionLogItem{item_id,
                                     percentage: rate,
                                     categories: if name.contains("3rd age") {
                                         categories + ", Third Age"
@@ -229,8 +242,7 @@ impl CollectionLogManager<> {
                                         categories
                                     },
                                     item_name: name,
-                                    preferred_name: preferred_name};
-                                    data_insert_separated.push(format_args!("(\"{}\", \"{}\", \"{}\", \"{}\", \"{}\")", item.item_id, item.item_name, item.preferred_name, item.percentage, item.categories));
+                                    preferred_name};
                                     items.push(item);
                                 }
                             }
@@ -249,7 +261,23 @@ impl CollectionLogManager<> {
             info!("Response JSON structure: {}", serde_json::to_string_pretty(&json)?);
         }
 
-        data_insert.push("ON CONFLICT(item_id) DO UPDATE SET item_name=excluded.item_name, preferred_name=excluded.preferred_name, percentage=excluded.percentage, categories=excluded.categories");
+        // Nothing parsed means the page changed or the response was bad; keep the items we already have
+        if items.is_empty() {
+            anyhow::bail!("No collection log items found in the wiki response");
+        }
+
+        let mut data_insert: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "INSERT INTO collection_log_items (item_id, item_name, preferred_name, percentage, categories) "
+        );
+        data_insert.push_values(&items, |mut row, item| {
+            row.push_bind(item.item_id)
+                .push_bind(&item.item_name)
+                .push_bind(&item.preferred_name)
+                .push_bind(item.percentage.to_string())
+                .push_bind(&item.categories);
+        });
+
+        data_insert.push(" ON CONFLICT(item_id) DO UPDATE SET item_name=excluded.item_name, preferred_name=excluded.preferred_name, percentage=excluded.percentage, categories=excluded.categories");
 
         // let please_god = data_insert.into_sql();
         // info!("{}", please_god);
@@ -263,51 +291,32 @@ impl CollectionLogManager<> {
         .await?;
 
         info!("Initialized collection log with {} items", items.len());
-        Ok(items.len().try_into().unwrap())
+        Ok(items.len() as u64)
     }
 
     pub async fn calculate_points(&self, item_name: &str) -> Option<i64> {
         let data = self.data.read().await;
         let completion_rate = data.completion_rates.get(item_name)?;
         let item_record = sqlx::query!(
-            "SELECT * FROM v_item_data WHERE item_name LIKE '%' || ? || '%' ORDER BY item_id",
+            r#"SELECT whitelist, clamp, clamp_cap AS "clamp_cap: f64" FROM v_item_data WHERE item_name LIKE '%' || ? || '%' ORDER BY item_id"#,
             item_name
         )
         .fetch_one(&self.db)
         .await
         .ok()?;
         
-        // Multi-tiered point calculation
-        let points = if *completion_rate <= 5.0 {
-            // Tier 3: Mega-rare items (≤5%)
-            // 5% -> 500 points
-            // 3% -> 1000 points
-            // 1% -> 15000 points
-            // 0.5% -> 30000 points
-            let base = 100.0;
-            let rarity_multiplier = (1.0 / completion_rate).powf(1.5) * 30.0;
-            //Is the item in a clamped category, and not whitelisted?
-            //Only checked here because the other percentage categories are nowhere near 3k
-            if item_record.whitelist == Some(0) && item_record.clamp > 0 {
-                (base * rarity_multiplier).clamp(0.0, 3000.0)
-            }
-            else {
-                base * rarity_multiplier
+        let rules = match ScoringRules::load(&self.db).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                error!("Failed to load scoring rules: {}", e);
+                return None;
             }
-        } else if *completion_rate <= 20.0 {
-            // Tier 2: Moderately rare items (5-20%)
-            // Linear interpolation between:
-            // 20% -> 200 points
-            // 5% -> 500 points
-            let progress = (20.0 - completion_rate) / 15.0; // 0 to 1 scale
-            200.0 + (progress * 300.0)
-        } else {
-            // Tier 1: Common items (>20%)
-            // Simple linear scaling
-            100.0 - (completion_rate * 0.5)
         };
 
-        Some(points.round() as i64)
+        //Is the item in a clamped category, and not whitelisted? Then its cap is the lowest of those categories'
+        let cap = (item_record.whitelist == Some(0) && item_record.clamp > 0)
+            .then(|| item_record.clamp_cap.unwrap_or(rules.clamp_cap));
+        Some(rules.clog_points(item_name, *completion_rate, cap))
     }
 
     pub async fn get_suggestions(&self, partial: &str) -> Vec<String> {
@@ -322,6 +331,49 @@ impl CollectionLogManager<> {
             .collect()
     }
 
+    /// Pets from the collection log matching `partial`
+    pub async fn get_pet_suggestions(&self, partial: &str) -> Vec<String> {
+        let pattern = format!("%{}%", partial);
+
+        let query_results = sqlx::query_scalar!(
+            r#"SELECT cli.item_name AS "item_name!: String"
+               FROM collection_log_items cli
+               JOIN v_categories_clogs vc ON vc.item_id = cli.item_id
+               WHERE vc.category = ? AND cli.item_name LIKE ?
+               GROUP BY cli.item_name
+               ORDER BY cli.item_name
+               LIMIT 25"#,
+            PET_CATEGORY,
+            pattern
+        )
+        .fetch_all(&self.db)
+        .await;
+
+        match query_results {
+            Ok(pets) => pets,
+            Err(e) => {
+                error!("Failed to look up pet suggestions: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Whether the item is one of the collection log's pets
+    pub async fn is_pet(&self, item_name: &str) -> bool {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (
+                 SELECT 1 FROM collection_log_items cli
+                 JOIN v_categories_clogs vc ON vc.item_id = cli.item_id
+                 WHERE vc.category = ? AND cli.item_name = ?
+               ) AS "is_pet!: bool""#,
+            PET_CATEGORY,
+            item_name
+        )
+        .fetch_one(&self.db)
+        .await
+        .unwrap_or(false)
+    }
+
     pub async fn get_category_suggestions(&self, partial: &str) -> Vec<String> {
         let partial = partial.to_lowercase();
 
@@ -331,7 +383,7 @@ impl CollectionLogManager<> {
         .fetch_all(&self.db)
         .await;
 
-        for (i, result) in query_results.unwrap().into_iter().enumerate() {
+        for result in query_results.unwrap().into_iter() {
             query_suggestions.push(result.category.unwrap());
         }
 
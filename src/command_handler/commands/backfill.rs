@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    EditMessage,
+};
+use sqlx::SqlitePool;
+use crate::config::ConfigKey;
+use crate::logger;
+use crate::runescape_tracker::{self, RunescapeTrackerKey};
+
+/// ADMIN: Runs the RuneLite channel's history since a date through auto-tracking, for when the bot was offline
+pub async fn handle_backfill(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let since = command.data.options
+        .iter()
+        .find(|opt| opt.name == "since")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Since date not provided"))?;
+
+    let Ok(since) = NaiveDate::parse_from_str(since.trim(), "%Y-%m-%d") else {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("'{}' is not a valid date, use YYYY-MM-DD.", since))
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    };
+
+    let (channel_id, tracker) = {
+        let data = ctx.data.read().await;
+        (
+            data.get::<ConfigKey>().and_then(|config| config.runelite_channel_id),
+            data.get::<RunescapeTrackerKey>().cloned(),
+        )
+    };
+    let (Some(channel_id), Some(tracker)) = (channel_id, tracker) else {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("`RUNELITE_CHANNEL_ID` isn't set, there's no channel to backfill.")
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(format!("Going through <#{}> since {}, progress is posted below.", channel_id, since))
+        ))
+        .await?;
+
+    // A long backfill outlives the interaction token, so progress and the result go in a channel message
+    let mut progress = command.channel_id.say(&ctx.http, "Backfilling...").await?;
+
+    let after = runescape_tracker::message_id_at(since.and_hms_opt(0, 0, 0).unwrap());
+    let message = match tracker.backfill(ctx, db, channel_id, after, Some(&mut progress)).await {
+        Ok(summary) if summary.failed > 0 => format!(
+            "Backfill since {} done: {}, see the bot's logs. Failed messages are retried on the next startup.",
+            since, summary.describe()
+        ),
+        Ok(summary) => format!("Backfill since {} done: {}.", since, summary.describe()),
+        Err(why) => format!("Backfill since {} stopped: {}. Run it again to pick up the rest.", since, why),
+    };
+
+    progress.edit(ctx, EditMessage::new().content(&message)).await?;
+
+    logger::log_action(ctx, &command.user.id.to_string(), "BACKFILL", &message).await?;
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::NaiveDateTime;
 use serenity::all::{
     CommandInteraction,
     CreateInteractionResponse,
@@ -6,8 +7,12 @@ use serenity::all::{
 };
 use sqlx::SqlitePool;
 use crate::command_handler::{CollectionLogManagerKey, format_points, format_number};
-use crate::rank_manager;
+use crate::command_handler::commands::submissions::{self, Submission, SubmissionKind};
+use crate::command_handler::commands::target::Target;
+use crate::rank_manager::{self, PointsUpdate};
 use crate::logger;
+use crate::ledger::{PointChange, Reason};
+use crate::screenshots::{self, Screenshot};
 
 pub async fn handle_clog(
     command: &CommandInteraction,
@@ -22,24 +27,17 @@ pub async fn handle_clog(
         .and_then(|opt| opt.value.as_str())
         .ok_or_else(|| anyhow::anyhow!("Item name not provided"))?;
 
-    let discord_id = command.user.id.to_string();
+    let target = Target::from_command(command);
 
     // Check if user already has this collection log entry
-    if let Ok(Some(existing_entry)) = sqlx::query!(
-        "SELECT timestamp FROM collection_log_entries 
-         WHERE discord_id = ? AND item_name = ?",
-        discord_id,
-        item_name
-    )
-    .fetch_optional(db)
-    .await
-    {
-        let timestamp = existing_entry.timestamp.expect("Timestamp should not be null");
+    if let Some(timestamp) = logged_at(db, &target.discord_id, item_name).await {
+        let who = if target.on_behalf() {format!("<@{}> has", target.discord_id)} else {"You've".to_string()};
         command
             .create_response(&ctx.http, CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::new()
                     .content(format!(
-                        "You've already logged {} in your collection log on {}!",
+                        "{} already logged {} in the collection log on {}!",
+                        who,
                         item_name,
                         timestamp.format("%B %d, %Y at %H:%M UTC")
                     ))
@@ -48,108 +46,66 @@ pub async fn handle_clog(
         return Ok(());
     }
 
-    // Get collection log manager from context data
-    let data = ctx.data.read().await;
-    let collection_log_manager = data.get::<CollectionLogManagerKey>()
-        .ok_or_else(|| anyhow::anyhow!("Collection log manager not found"))?;
-
-    // Calculate collection log points
-    if let Some(points) = collection_log_manager.calculate_points(item_name).await {
-        // Insert or update user
-        sqlx::query!(
-            "INSERT INTO users (discord_id, points, total_drops) 
-             VALUES (?, 0, 0)
-             ON CONFLICT(discord_id) DO NOTHING",
-            discord_id
-        )
-        .execute(db)
-        .await?;
+    // A mod recording it for someone else has already checked it
+    let attachment = submissions::screenshot_attachment(command);
+    if !target.on_behalf() && submissions::approval_required(ctx).await {
+        let Some(attachment) = attachment else {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("Collection log entries need a mod's approval, attach a `screenshot` of the item and try again.")
+                        .ephemeral(true)
+                ))
+                .await?;
+            return Ok(());
+        };
 
-        let item_record = sqlx::query!("SELECT item_id, item_name FROM collection_log_items WHERE item_name = ? GROUP BY item_name ORDER BY percentage", item_name)
-        .fetch_one(db)
-        .await?;
+        let known_item = {
+            let data = ctx.data.read().await;
+            let collection_log_manager = data.get::<CollectionLogManagerKey>()
+                .ok_or_else(|| anyhow::anyhow!("Collection log manager not found"))?;
+            collection_log_manager.calculate_points(item_name).await.is_some()
+        };
+        if !known_item {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("Item '{}' not found in collection log.", item_name))
+                ))
+                .await?;
+            return Ok(());
+        }
 
-        // Record the collection log entry
-        sqlx::query!(
-            "INSERT INTO collection_log_entries (discord_id, item_name, points, item_id) VALUES (?, ?, ?, ?)",
-            discord_id,
+        return submissions::submit(command, ctx, db, &Submission {
+            kind: SubmissionKind::Clog,
             item_name,
-            points,
-            item_record.item_id,
-        )
-        .execute(db)
-        .await?;
-        
-        // Log the collection log entry
-        logger::log_action(
-            ctx,
-            &discord_id,
-            "ADDED CLOG",
-            &format!("{} ({} pts)", item_name, format_number(points))
-        ).await?;
-
-        // Add points and check for rank up
-        let points_update = rank_manager::add_points(
-            ctx,
-            &discord_id,
-            &command.member.as_ref()
-                .and_then(|m| Some(m.display_name()))
-                .unwrap_or(&command.user.name),
-            points,
-            db
-        ).await?;
-
-        // Format response message
-        let message_content = if !points_update.crossed_ranks.is_empty() {
-            // User ranked up!
-            let rank_text = if points_update.crossed_ranks.len() == 1 {
-                format!("the {} rank", points_update.crossed_ranks[0])
-            } else {
-                let ranks: Vec<_> = points_update.crossed_ranks.iter().map(|r| r.as_str()).collect();
-                match ranks.len() {
-                    2 => format!("the {} and {} ranks", ranks[0], ranks[1]),
-                    _ => {
-                        let (last, rest) = ranks.split_last().unwrap();
-                        format!("the {}, and {} ranks", rest.join(", "), last)
-                    }
-                }
-            };
-            
-            let next_rank_info = if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
-                format!(" Next rank at {} points for {}!", format_number(*next_rank_points), next_rank_name)
-            } else {
-                "".to_string()
-            };
-            
-            format!(
-                "🎆 🎇 **RANK UP!** 🎇 🎆\nCollection log entry recorded: {} (+{} points)! You now have {} and achieved {}!{}",
-                item_name,
-                format_number(points),
-                format_points(points_update.new_points),
-                rank_text,
-                next_rank_info
-            )
-        } else if let Some((next_rank_points, next_rank_name)) = points_update.next_rank {
-            format!(
-                "Collection log entry recorded: {} (+{} points)! You now have {}. Next rank at {} points for {}!",
-                item_name,
-                format_number(points),
-                format_points(points_update.new_points),
-                format_number(next_rank_points),
-                next_rank_name
-            )
-        } else {
-            format!(
-                "Collection log entry recorded: {} (+{} points)! You now have {}!",
-                item_name,
-                format_number(points),
-                format_points(points_update.new_points)
-            )
-        };
+            quantity: 1,
+            value: None,
+            source: None,
+            dropped_at: None,
+            screenshot: &screenshots::archive(ctx, attachment).await,
+            shares: &[],
+        }).await;
+    }
 
+    let screenshot = match attachment {
+        Some(attachment) => screenshots::archive(ctx, attachment).await,
+        None => Screenshot::default(),
+    };
+
+    // Calculate collection log points
+    if let Some((points, points_update)) = record_clog(ctx, db, &NewClog {
+        discord_id: &target.discord_id,
+        user_name: &target.user_name,
+        item_name,
+        screenshot: &screenshot,
+        actor_id: &target.actor_id,
+        when: None,
+    }).await? {
         command
             .create_response(&ctx.http, CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new().content(message_content)
+                CreateInteractionResponseMessage::new()
+                    .content(target.reply(clog_recorded_message(item_name, points, &points_update)))
             ))
             .await?;
     } else {
@@ -162,4 +118,149 @@ pub async fn handle_clog(
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// When the member logged this item, if they already have
+pub async fn logged_at(db: &SqlitePool, discord_id: &str, item_name: &str) -> Option<NaiveDateTime> {
+    sqlx::query!(
+        "SELECT timestamp FROM collection_log_entries 
+         WHERE discord_id = ? AND item_name = ?",
+        discord_id,
+        item_name
+    )
+    .fetch_optional(db)
+    .await
+    .ok()
+    .flatten()
+    .map(|entry| entry.timestamp.expect("Timestamp should not be null"))
+}
+
+/// A collection log entry ready to be recorded, from /clog, /admin_clog or an approved submission
+pub struct NewClog<'a> {
+    pub discord_id: &'a str,
+    pub user_name: &'a str,
+    pub item_name: &'a str,
+    pub screenshot: &'a Screenshot,
+    /// Discord ID of whoever recorded it: the member, or the mod who added or approved it
+    pub actor_id: &'a str,
+    /// When the item was received, `None` for now
+    pub when: Option<NaiveDateTime>,
+}
+
+/// Inserts the entry, logs it and awards its points. Returns the points it gave, or `None` if the
+/// item isn't in the collection log
+pub async fn record_clog(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    clog: &NewClog<'_>,
+) -> Result<Option<(i64, PointsUpdate)>> {
+    // Get collection log manager from context data
+    let data = ctx.data.read().await;
+    let collection_log_manager = data.get::<CollectionLogManagerKey>()
+        .ok_or_else(|| anyhow::anyhow!("Collection log manager not found"))?;
+
+    let Some(points) = collection_log_manager.calculate_points(clog.item_name).await else {
+        return Ok(None);
+    };
+
+    // Insert or update user
+    sqlx::query!(
+        "INSERT INTO users (discord_id, points, total_drops) 
+         VALUES (?, 0, 0)
+         ON CONFLICT(discord_id) DO NOTHING",
+        clog.discord_id
+    )
+    .execute(db)
+    .await?;
+
+    let item_record = sqlx::query!("SELECT item_id, item_name FROM collection_log_items WHERE item_name = ? GROUP BY item_name ORDER BY percentage", clog.item_name)
+    .fetch_one(db)
+    .await?;
+
+    // Record the collection log entry
+    let entry_id = sqlx::query!(
+        "INSERT INTO collection_log_entries (discord_id, item_name, points, item_id, timestamp, screenshot_url, screenshot_path)
+         VALUES (?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?)",
+        clog.discord_id,
+        clog.item_name,
+        points,
+        item_record.item_id,
+        clog.when,
+        clog.screenshot.url,
+        clog.screenshot.path,
+    )
+    .execute(db)
+    .await?
+    .last_insert_rowid();
+    
+    // Log the collection log entry
+    let mut details = format!("{} ({} pts)", clog.item_name, format_number(points));
+    if clog.actor_id != clog.discord_id {
+        details += format!(", recorded by <@{}>", clog.actor_id).as_str();
+    }
+    details += &clog.screenshot.link();
+    logger::log_action(ctx, clog.discord_id, "ADDED CLOG", &details).await?;
+
+    // Add points and check for rank up
+    let points_update = rank_manager::add_points(
+        ctx,
+        clog.discord_id,
+        clog.user_name,
+        PointChange::new(points, Reason::Clog)
+            .source("collection_log_entries", entry_id)
+            .actor(clog.actor_id),
+        db
+    ).await?;
+
+    Ok(Some((points, points_update)))
+}
+
+/// The reply for a recorded collection log entry, including any rank ups
+pub fn clog_recorded_message(item_name: &str, points: i64, points_update: &PointsUpdate) -> String {
+    if !points_update.crossed_ranks.is_empty() {
+        // User ranked up!
+        let rank_text = if points_update.crossed_ranks.len() == 1 {
+            format!("the {} rank", points_update.crossed_ranks[0])
+        } else {
+            let ranks: Vec<_> = points_update.crossed_ranks.iter().map(|r| r.as_str()).collect();
+            match ranks.len() {
+                2 => format!("the {} and {} ranks", ranks[0], ranks[1]),
+                _ => {
+                    let (last, rest) = ranks.split_last().unwrap();
+                    format!("the {}, and {} ranks", rest.join(", "), last)
+                }
+            }
+        };
+        
+        let next_rank_info = if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
+            format!(" Next rank at {} points for {}!", format_number(*next_rank_points), next_rank_name)
+        } else {
+            "".to_string()
+        };
+        
+        format!(
+            "🎆 🎇 **RANK UP!** 🎇 🎆\nCollection log entry recorded: {} (+{} points)! You now have {} and achieved {}!{}",
+            item_name,
+            format_number(points),
+            format_points(points_update.new_points),
+            rank_text,
+            next_rank_info
+        )
+    } else if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
+        format!(
+            "Collection log entry recorded: {} (+{} points)! You now have {}. Next rank at {} points for {}!",
+            item_name,
+            format_number(points),
+            format_points(points_update.new_points),
+            format_number(*next_rank_points),
+            next_rank_name
+        )
+    } else {
+        format!(
+            "Collection log entry recorded: {} (+{} points)! You now have {}!",
+            item_name,
+            format_number(points),
+            format_points(points_update.new_points)
+        )
+    }
+}
@@ -7,17 +7,21 @@ use serenity::all::{
 use sqlx::SqlitePool;
 use crate::rank_manager;
 use crate::logger;
+use crate::ledger::{self, PointChange, Reason};
+use crate::screenshots;
+use crate::command_handler::commands::target::Target;
 
 pub async fn handle_clog_remove(
     command: &CommandInteraction,
     ctx: &serenity::prelude::Context,
     db: &SqlitePool,
 ) -> Result<()> {
-    let discord_id = command.user.id.to_string();
+    let target = Target::from_command(command);
+    let discord_id = target.discord_id.as_str();
     
     // Get the user's most recent collection log entries (top 10)
     let recent_entries = sqlx::query!(
-        "SELECT id, item_name, points, timestamp 
+        "SELECT id, item_name, points, timestamp, screenshot_url, screenshot_path 
          FROM collection_log_entries 
          WHERE discord_id = ? 
          ORDER BY timestamp DESC 
@@ -31,7 +35,11 @@ pub async fn handle_clog_remove(
         command
             .create_response(&ctx.http, CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::new()
-                    .content("You don't have any recorded collection log entries to remove.")
+                    .content(if target.on_behalf() {
+                        format!("<@{}> doesn't have any recorded collection log entries to remove.", discord_id)
+                    } else {
+                        "You don't have any recorded collection log entries to remove.".to_string()
+                    })
             ))
             .await?;
         return Ok(());
@@ -53,20 +61,25 @@ pub async fn handle_clog_remove(
         },
         None => {
             // If no ID provided, show the list of recent collection log entries
-            let mut entries_list = String::from("Your most recent collection log entries:\n");
+            let mut entries_list = if target.on_behalf() {
+                format!("<@{}>'s most recent collection log entries:\n", discord_id)
+            } else {
+                String::from("Your most recent collection log entries:\n")
+            };
             
             for entry in &recent_entries {
                 let timestamp = entry.timestamp.expect("Timestamp should not be null");
                 entries_list.push_str(&format!(
-                    "ID {}: {} ({} pts) - {}\n",
+                    "ID {}: {} ({} pts) - {}{}\n",
                     entry.id,
                     entry.item_name,
                     entry.points,
-                    timestamp.format("%Y-%m-%d %H:%M:%S")
+                    timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    screenshots::describe(entry.screenshot_url.as_deref(), entry.screenshot_path.as_deref())
                 ));
             }
             
-            entries_list.push_str("\nTo remove an entry, use `/clog_remove id:<entry_id>`");
+            entries_list.push_str(&format!("\nTo remove an entry, use `/{} id:<entry_id>`", command.data.name));
             
             command
                 .create_response(&ctx.http, CreateInteractionResponse::Message(
@@ -80,7 +93,7 @@ pub async fn handle_clog_remove(
     
     // Find the collection log entry with the given ID
     let entry_to_remove = sqlx::query!(
-        "SELECT id, item_name, points 
+        "SELECT id, item_name, points, screenshot_url, screenshot_path 
          FROM collection_log_entries 
          WHERE id = ? AND discord_id = ?",
         entry_id,
@@ -105,25 +118,34 @@ pub async fn handle_clog_remove(
             // Log the collection log entry removal
             logger::log_action(
                 ctx,
-                &discord_id,
+                discord_id,
                 "REMOVED CLOG",
-                &format!("{} ({} pts) [ID: {}]", entry.item_name, entry.points, entry.id)
+                &format!(
+                    "{} ({} pts) [ID: {}]{}{}",
+                    entry.item_name,
+                    entry.points,
+                    entry.id,
+                    screenshots::describe(entry.screenshot_url.as_deref(), entry.screenshot_path.as_deref()),
+                    target.removed_by()
+                )
             ).await?;
             
+            // Deduct points from user, in the same transaction so the entry can't go without its points
+            if entry.points > 0 {
+                ledger::record(
+                    &mut *tx,
+                    discord_id,
+                    &PointChange::new(-entry.points, Reason::ClogRemoved) // Negative to deduct points
+                        .source("collection_log_entries", entry.id)
+                        .actor(&target.actor_id),
+                ).await?;
+            }
+            
             // Commit transaction
             tx.commit().await?;
             
-            // Deduct points from user
             if entry.points > 0 {
-                let points_update = rank_manager::add_points(
-                    ctx,
-                    &discord_id,
-                    &command.member.as_ref()
-                        .and_then(|m| Some(m.display_name()))
-                        .unwrap_or(&command.user.name),
-                    -entry.points, // Negative to deduct points
-                    db
-                ).await?;
+                let points_update = rank_manager::points_recorded(ctx, discord_id, &target.user_name, -entry.points, db).await?;
                 
                 let message = if !points_update.crossed_ranks.is_empty() {
                     // User ranked down
@@ -156,17 +178,17 @@ pub async fn handle_clog_remove(
                 
                 command
                     .create_response(&ctx.http, CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::new().content(message)
+                        CreateInteractionResponseMessage::new().content(target.reply(message))
                     ))
                     .await?;
             } else {
                 command
                     .create_response(&ctx.http, CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
-                            .content(format!(
+                            .content(target.reply(format!(
                                 "Collection log entry removed: {} (0 pts). No points were deducted.",
                                 entry.item_name
-                            ))
+                            )))
                     ))
                     .await?;
             }
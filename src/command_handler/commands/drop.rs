@@ -1,13 +1,20 @@
 use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
 use serenity::all::{
     CommandInteraction,
     CreateInteractionResponse,
     CreateInteractionResponseMessage,
 };
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 use crate::command_handler::{PriceManagerKey, format_gp, format_points, format_number};
-use crate::rank_manager;
+use crate::command_handler::commands::submissions::{self, Submission, SubmissionKind};
+use crate::command_handler::commands::target::Target;
+use crate::rank_manager::{self, PointsUpdate};
 use crate::logger;
+use crate::scoring::ScoringRules;
+use crate::ledger::{self, PointChange, Reason};
+use crate::screenshots::{self, Screenshot};
+use crate::splits::{self, Share};
 
 pub async fn handle_drop(
     command: &CommandInteraction,
@@ -28,125 +35,174 @@ pub async fn handle_drop(
         .and_then(|opt| opt.value.as_i64())
         .unwrap_or(1);
 
+    let source = options
+        .iter()
+        .find(|opt| opt.name == "source")
+        .and_then(|opt| opt.value.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+
+    let when = match options
+        .iter()
+        .find(|opt| opt.name == "when")
+        .and_then(|opt| opt.value.as_str())
+        .map(parse_when)
+        .transpose()
+    {
+        Ok(when) => when,
+        Err(message) => {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new().content(message)
+                ))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let target = Target::from_command(command);
+
+    // Teammates to split the drop with, evenly unless `shares` says otherwise
+    let teammates_text = options
+        .iter()
+        .find(|opt| opt.name == "teammates")
+        .and_then(|opt| opt.value.as_str());
+    let teammates: Vec<_> = teammates_text
+        .map(splits::parse_teammates)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|user_id| user_id.to_string() != target.discord_id)
+        .collect();
+    let shares_text = options
+        .iter()
+        .find(|opt| opt.name == "shares")
+        .and_then(|opt| opt.value.as_str());
+
+    let weights = if teammates_text.is_some() && teammates.is_empty() {
+        Err("Mention the teammates to split with, e.g. `@Alice @Bob`.".to_string())
+    } else if teammates.is_empty() && shares_text.is_some() {
+        Err("`shares` only applies to a split drop, mention your `teammates` too.".to_string())
+    } else {
+        splits::parse_weights(shares_text, teammates.len() + 1)
+    };
+    let weights = match weights {
+        Ok(weights) => weights,
+        Err(message) => {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(message)
+                        .ephemeral(true)
+                ))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut shares = Vec::new();
+    if !teammates.is_empty() {
+        shares.push(Share {
+            discord_id: target.discord_id.clone(),
+            user_name: target.user_name.clone(),
+            weight: weights[0],
+        });
+        for (user_id, weight) in teammates.iter().zip(&weights[1..]) {
+            let discord_id = user_id.to_string();
+            shares.push(Share {
+                user_name: splits::user_name(ctx, &discord_id).await,
+                discord_id,
+                weight: *weight,
+            });
+        }
+    }
+
+    // With approval on, the drop is only recorded once a mod has seen the screenshot.
+    // A mod recording it for someone else has already checked it
+    let needs_approval = !target.on_behalf() && submissions::approval_required(ctx).await;
+    let attachment = submissions::screenshot_attachment(command);
+    if needs_approval && attachment.is_none() {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("Drops need a mod's approval, attach a `screenshot` of the drop and try again.")
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
     // Get price manager from context data
     let data = ctx.data.read().await;
     let price_manager = data.get::<PriceManagerKey>()
         .ok_or_else(|| anyhow::anyhow!("Price manager not found"))?;
 
-    // Get item price
-    if let Some(value) = price_manager.get_item_price(item_name).await {
-        let discord_id = command.user.id.to_string();
+    // Get item price, either the current one or the snapshot nearest to when the drop happened
+    let price = match when {
+        Some(at) => match price_manager.get_snapshot_closest_to(item_name, at).await? {
+            Some(snapshot) => Some((snapshot.value, Some(snapshot.timestamp))),
+            None if price_manager.get_item_mapping(item_name).await.is_some() => {
+                command
+                    .create_response(&ctx.http, CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(format!("No price history for '{}' yet, leave out `when` to use the current price.", item_name))
+                    ))
+                    .await?;
+                return Ok(());
+            }
+            None => None,
+        },
+        None => price_manager.get_item_price(item_name).await.map(|value| (value, None)),
+    };
+
+    if let Some((value, priced_at)) = price {
         let total_value = value * quantity;
-        let points = total_value / 100_000; // 1 point per 100,000 gp
+        let screenshot = match attachment {
+            Some(attachment) => screenshots::archive(ctx, attachment).await,
+            None => Screenshot::default(),
+        };
 
-        // Insert or update user
-        sqlx::query!(
-            "INSERT INTO users (discord_id, points, total_drops) 
-             VALUES (?, 0, 0)
-             ON CONFLICT(discord_id) DO NOTHING",
-            discord_id
-        )
-        .execute(db)
-        .await?;
+        if needs_approval {
+            return submissions::submit(command, ctx, db, &Submission {
+                kind: SubmissionKind::Drop,
+                item_name,
+                quantity,
+                value: Some(total_value),
+                source,
+                dropped_at: when,
+                screenshot: &screenshot,
+                shares: &shares,
+            }).await;
+        }
 
-        // Record the drop
-        sqlx::query!(
-            "INSERT INTO drops (discord_id, item_name, value, quantity) VALUES (?, ?, ?, ?)",
-            discord_id,
+        let new_drop = NewDrop {
+            discord_id: &target.discord_id,
+            user_name: &target.user_name,
             item_name,
+            quantity,
             total_value,
-            quantity
-        )
-        .execute(db)
-        .await?;
-
-        // Log the drop
-        logger::log_action(
-            ctx,
-            &discord_id,
-            "ADDED DROP",
-            &format!("{}x {} ({})", quantity, item_name, format_gp(total_value))
-        ).await?;
+            source,
+            when,
+            screenshot: &screenshot,
+            actor_id: &target.actor_id,
+        };
 
-        // Update total drops
-        sqlx::query!(
-            "UPDATE users 
-             SET total_drops = total_drops + ?
-             WHERE discord_id = ?",
-            quantity,
-            discord_id
-        )
-        .execute(db)
-        .await?;
+        let value_text = match priced_at {
+            Some(priced_at) => format!("{} at {} prices", format_gp(total_value), priced_at.format("%Y-%m-%d %H:%M")),
+            None => format_gp(total_value),
+        };
 
-        // Add points and check for rank up
-        let points_update = rank_manager::add_points(
-            ctx,
-            &discord_id,
-            &command.member.as_ref()
-                .and_then(|m| Some(m.display_name()))
-                .unwrap_or(&command.user.name),
-            points,
-            db
-        ).await?;
-
-        // Format response message
-        let message_content = if !points_update.crossed_ranks.is_empty() {
-            // User ranked up!
-            let rank_text = if points_update.crossed_ranks.len() == 1 {
-                format!("the {} rank", points_update.crossed_ranks[0])
-            } else {
-                let ranks: Vec<_> = points_update.crossed_ranks.iter().map(|r| r.as_str()).collect();
-                match ranks.len() {
-                    2 => format!("the {} and {} ranks", ranks[0], ranks[1]),
-                    _ => {
-                        let (last, rest) = ranks.split_last().unwrap();
-                        format!("the {}, and {} ranks", rest.join(", "), last)
-                    }
-                }
-            };
-            
-            let next_rank_info = if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
-                format!(" Next rank at {} points for {}!", format_number(*next_rank_points), next_rank_name)
-            } else {
-                "".to_string()
-            };
-            
-            format!(
-                "🎆 🎇 **RANK UP!** 🎇 🎆\nDrop recorded: {}x {} ({}) (+{} points)! You now have {} and achieved {}!{}",
-                format_number(quantity),
-                item_name,
-                format_gp(total_value),
-                format_number(points),
-                format_points(points_update.new_points),
-                rank_text,
-                next_rank_info
-            )
-        } else if let Some((next_rank_points, next_rank_name)) = points_update.next_rank {
-            format!(
-                "Drop recorded: {}x {} ({}) (+{} points)! You now have {}. Next rank at {} points for {}!",
-                format_number(quantity),
-                item_name,
-                format_gp(total_value),
-                format_number(points),
-                format_points(points_update.new_points),
-                format_number(next_rank_points),
-                next_rank_name
-            )
+        let message = if shares.is_empty() {
+            let (points, points_update) = record_drop(ctx, db, &new_drop).await?;
+            drop_recorded_message(quantity, item_name, &value_text, points, &points_update)
         } else {
-            format!(
-                "Drop recorded: {}x {} ({}) (+{} points)! You now have {}!",
-                format_number(quantity),
-                item_name,
-                format_gp(total_value),
-                format_number(points),
-                format_points(points_update.new_points)
-            )
+            let recorded = record_split_drop(ctx, db, &new_drop, &shares).await?;
+            split_recorded_message(quantity, item_name, &value_text, &shares, &recorded)
         };
 
         command
             .create_response(&ctx.http, CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::new().content(message_content)
+                CreateInteractionResponseMessage::new()
+                    .content(target.reply(message))
             ))
             .await?;
     } else {
@@ -159,4 +215,269 @@ pub async fn handle_drop(
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// A drop ready to be recorded, from /drop, /admin_drop or an approved submission
+pub struct NewDrop<'a> {
+    pub discord_id: &'a str,
+    pub user_name: &'a str,
+    pub item_name: &'a str,
+    pub quantity: i64,
+    pub total_value: i64,
+    pub source: Option<&'a str>,
+    pub when: Option<NaiveDateTime>,
+    pub screenshot: &'a Screenshot,
+    /// Discord ID of whoever recorded it: the member, or the mod who added or approved it
+    pub actor_id: &'a str,
+}
+
+/// Inserts the drop, logs it and awards its points. Returns the points it gave
+pub async fn record_drop(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    drop: &NewDrop<'_>,
+) -> Result<(i64, PointsUpdate)> {
+    let points = ScoringRules::load(db).await?.drop_points(drop.item_name, drop.quantity, drop.total_value);
+    let recipient = Share {
+        discord_id: drop.discord_id.to_string(),
+        user_name: drop.user_name.to_string(),
+        weight: 1,
+    };
+
+    let mut tx = db.begin().await?;
+    let details = insert_drop(&mut tx, drop, &recipient, drop.total_value, points, None).await?;
+    tx.commit().await?;
+
+    let points_update = drop_recorded(ctx, db, &recipient, points, &details).await?;
+    Ok((points, points_update))
+}
+
+/// Records a group drop with a row per participant holding their share of the value and points.
+/// `shares` starts with the drop's own recipient. The split and every share are written in one
+/// transaction. Returns the points each share gave, in the same order
+pub async fn record_split_drop(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    drop: &NewDrop<'_>,
+    shares: &[Share],
+) -> Result<Vec<(i64, PointsUpdate)>> {
+    let points = ScoringRules::load(db).await?.drop_points(drop.item_name, drop.quantity, drop.total_value);
+
+    let mut tx = db.begin().await?;
+    let split_id = sqlx::query!(
+        "INSERT INTO drop_splits (item_name, quantity, total_value, total_points, source, actor_id)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        drop.item_name,
+        drop.quantity,
+        drop.total_value,
+        points,
+        drop.source,
+        drop.actor_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    let weights: Vec<i64> = shares.iter().map(|share| share.weight).collect();
+    let values = splits::divide(drop.total_value, &weights);
+    let share_points = splits::divide(points, &weights);
+
+    let mut details = Vec::new();
+    for (i, share) in shares.iter().enumerate() {
+        details.push(insert_drop(&mut tx, drop, share, values[i], share_points[i], Some(split_id)).await?);
+    }
+    tx.commit().await?;
+
+    let mut recorded = Vec::new();
+    for (i, share) in shares.iter().enumerate() {
+        let points_update = drop_recorded(ctx, db, share, share_points[i], &details[i]).await?;
+        recorded.push((share_points[i], points_update));
+    }
+
+    Ok(recorded)
+}
+
+/// One drops row: inserts it and awards its points to the recipient. Returns the line for the bot log,
+/// which `drop_recorded` posts once the transaction is committed
+async fn insert_drop(
+    conn: &mut SqliteConnection,
+    drop: &NewDrop<'_>,
+    recipient: &Share,
+    value: i64,
+    points: i64,
+    split_id: Option<i64>,
+) -> Result<String> {
+    // Insert or update user
+    sqlx::query!(
+        "INSERT INTO users (discord_id, points, total_drops) 
+         VALUES (?, 0, 0)
+         ON CONFLICT(discord_id) DO NOTHING",
+        recipient.discord_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Record the drop. A split is still one drop, only the recipient's row counts towards total_drops
+    let share = split_id.map(|_| recipient.weight);
+    let counted = recipient.discord_id == drop.discord_id;
+    let drop_id = sqlx::query!(
+        "INSERT INTO drops (discord_id, item_name, value, quantity, source, points, timestamp, screenshot_url, screenshot_path, split_id, share, counted)
+         VALUES (?, ?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?, ?, ?, ?)",
+        recipient.discord_id,
+        drop.item_name,
+        value,
+        drop.quantity,
+        drop.source,
+        points,
+        drop.when,
+        drop.screenshot.url,
+        drop.screenshot.path,
+        split_id,
+        share,
+        counted
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    // Update total drops
+    if counted {
+        sqlx::query!(
+            "UPDATE users 
+             SET total_drops = total_drops + ?
+             WHERE discord_id = ?",
+            drop.quantity,
+            recipient.discord_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    // Add points
+    ledger::record(
+        &mut *conn,
+        &recipient.discord_id,
+        &PointChange::new(points, Reason::Drop)
+            .source("drops", drop_id)
+            .actor(drop.actor_id),
+    ).await?;
+
+    let mut details = match drop.source {
+        Some(source) => format!("{}x {} from {} ({})", drop.quantity, drop.item_name, source, format_gp(value)),
+        None => format!("{}x {} ({})", drop.quantity, drop.item_name, format_gp(value)),
+    };
+    if let Some(split_id) = split_id {
+        details += format!(" [split #{}]", split_id).as_str();
+    }
+    if drop.actor_id != recipient.discord_id {
+        details += format!(", recorded by <@{}>", drop.actor_id).as_str();
+    }
+    details += &drop.screenshot.link();
+    Ok(details)
+}
+
+/// Logs a committed drops row and checks the recipient for a rank up
+async fn drop_recorded(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    recipient: &Share,
+    points: i64,
+    details: &str,
+) -> Result<PointsUpdate> {
+    logger::log_action(ctx, &recipient.discord_id, "ADDED DROP", details).await?;
+    rank_manager::points_recorded(ctx, &recipient.discord_id, &recipient.user_name, points, db).await
+}
+
+/// The reply for a split drop, with each participant's points
+pub fn split_recorded_message(quantity: i64, item_name: &str, value_text: &str, shares: &[Share], recorded: &[(i64, PointsUpdate)]) -> String {
+    let lines: Vec<String> = shares
+        .iter()
+        .zip(recorded)
+        .map(|(share, (points, points_update))| format!(
+            "• <@{}>: +{} points, now {}{}",
+            share.discord_id,
+            format_number(*points),
+            format_points(points_update.new_points),
+            splits::rank_note(points_update)
+        ))
+        .collect();
+
+    format!(
+        "Drop recorded: {}x {} ({}) split {} ways!\n{}",
+        format_number(quantity),
+        item_name,
+        value_text,
+        shares.len(),
+        lines.join("\n")
+    )
+}
+
+/// The reply for a recorded drop, including any rank ups
+pub fn drop_recorded_message(quantity: i64, item_name: &str, value_text: &str, points: i64, points_update: &PointsUpdate) -> String {
+    if !points_update.crossed_ranks.is_empty() {
+        // User ranked up!
+        let rank_text = if points_update.crossed_ranks.len() == 1 {
+            format!("the {} rank", points_update.crossed_ranks[0])
+        } else {
+            let ranks: Vec<_> = points_update.crossed_ranks.iter().map(|r| r.as_str()).collect();
+            match ranks.len() {
+                2 => format!("the {} and {} ranks", ranks[0], ranks[1]),
+                _ => {
+                    let (last, rest) = ranks.split_last().unwrap();
+                    format!("the {}, and {} ranks", rest.join(", "), last)
+                }
+            }
+        };
+        
+        let next_rank_info = if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
+            format!(" Next rank at {} points for {}!", format_number(*next_rank_points), next_rank_name)
+        } else {
+            "".to_string()
+        };
+        
+        format!(
+            "🎆 🎇 **RANK UP!** 🎇 🎆\nDrop recorded: {}x {} ({}) (+{} points)! You now have {} and achieved {}!{}",
+            format_number(quantity),
+            item_name,
+            value_text,
+            format_number(points),
+            format_points(points_update.new_points),
+            rank_text,
+            next_rank_info
+        )
+    } else if let Some((next_rank_points, next_rank_name)) = &points_update.next_rank {
+        format!(
+            "Drop recorded: {}x {} ({}) (+{} points)! You now have {}. Next rank at {} points for {}!",
+            format_number(quantity),
+            item_name,
+            value_text,
+            format_number(points),
+            format_points(points_update.new_points),
+            format_number(*next_rank_points),
+            next_rank_name
+        )
+    } else {
+        format!(
+            "Drop recorded: {}x {} ({}) (+{} points)! You now have {}!",
+            format_number(quantity),
+            item_name,
+            value_text,
+            format_number(points),
+            format_points(points_update.new_points)
+        )
+    }
+}
+
+/// Parses the `when` option as a UTC date or date and time, which can't be in the future
+fn parse_when(value: &str) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    let at = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.and_hms_opt(0, 0, 0).unwrap()))
+        .map_err(|_| format!("'{}' is not a valid time, use YYYY-MM-DD or YYYY-MM-DD HH:MM (UTC).", value))?;
+
+    if at > Utc::now().naive_utc() {
+        return Err("The drop can't be in the future.".to_string());
+    }
+
+    Ok(at)
+}
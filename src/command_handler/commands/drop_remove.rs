@@ -8,17 +8,21 @@ use sqlx::SqlitePool;
 use crate::command_handler::format_gp;
 use crate::rank_manager;
 use crate::logger;
+use crate::ledger::{self, PointChange, Reason};
+use crate::screenshots;
+use crate::command_handler::commands::target::Target;
 
 pub async fn handle_drop_remove(
     command: &CommandInteraction,
     ctx: &serenity::prelude::Context,
     db: &SqlitePool,
 ) -> Result<()> {
-    let discord_id = command.user.id.to_string();
+    let target = Target::from_command(command);
+    let discord_id = target.discord_id.as_str();
     
     // Get the user's most recent drops (top 10)
     let recent_drops = sqlx::query!(
-        "SELECT id, item_name, value, quantity, timestamp 
+        "SELECT id, item_name, value, quantity, timestamp, screenshot_url, screenshot_path, split_id 
          FROM drops 
          WHERE discord_id = ? 
          ORDER BY timestamp DESC 
@@ -32,7 +36,11 @@ pub async fn handle_drop_remove(
         command
             .create_response(&ctx.http, CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::new()
-                    .content("You don't have any recorded drops to remove.")
+                    .content(if target.on_behalf() {
+                        format!("<@{}> doesn't have any recorded drops to remove.", discord_id)
+                    } else {
+                        "You don't have any recorded drops to remove.".to_string()
+                    })
             ))
             .await?;
         return Ok(());
@@ -54,21 +62,27 @@ pub async fn handle_drop_remove(
         },
         None => {
             // If no ID provided, show the list of recent drops
-            let mut drops_list = String::from("Your most recent drops:\n");
+            let mut drops_list = if target.on_behalf() {
+                format!("<@{}>'s most recent drops:\n", discord_id)
+            } else {
+                String::from("Your most recent drops:\n")
+            };
             
             for drop in &recent_drops {
                 let timestamp = drop.timestamp.expect("Timestamp should not be null");
                 drops_list.push_str(&format!(
-                    "ID {}: {}x {} ({}) - {}\n",
+                    "ID {}: {}x {} ({}{}) - {}{}\n",
                     drop.id,
                     drop.quantity,
                     drop.item_name,
                     format_gp(drop.value),
-                    timestamp.format("%Y-%m-%d %H:%M:%S")
+                    drop.split_id.map(|split_id| format!(", share of split #{}", split_id)).unwrap_or_default(),
+                    timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    screenshots::describe(drop.screenshot_url.as_deref(), drop.screenshot_path.as_deref())
                 ));
             }
             
-            drops_list.push_str("\nTo remove a drop, use `/drop_remove id:<drop_id>`");
+            drops_list.push_str(&format!("\nTo remove a drop, use `/{} id:<drop_id>`", command.data.name));
             
             command
                 .create_response(&ctx.http, CreateInteractionResponse::Message(
@@ -82,7 +96,7 @@ pub async fn handle_drop_remove(
     
     // Find the drop with the given ID
     let drop_to_remove = sqlx::query!(
-        "SELECT id, item_name, value, quantity 
+        "SELECT id, item_name, value, quantity, points, counted, screenshot_url, screenshot_path 
          FROM drops 
          WHERE id = ? AND discord_id = ?",
         drop_id,
@@ -93,22 +107,24 @@ pub async fn handle_drop_remove(
     
     match drop_to_remove {
         Some(drop) => {
-            // Calculate points to deduct
-            let points_to_deduct = drop.value / 100_000; // 1 point per 100,000 gp
+            // Take back exactly what the drop awarded
+            let points_to_deduct = drop.points;
             
             // Begin transaction
             let mut tx = db.begin().await?;
             
-            // Decrease user's total_drops
-            sqlx::query!(
-                "UPDATE users 
-                 SET total_drops = total_drops - ? 
-                 WHERE discord_id = ?",
-                drop.quantity,
-                discord_id
-            )
-            .execute(&mut *tx)
-            .await?;
+            // Decrease user's total_drops, a teammate's share of a split never added to it
+            if drop.counted != 0 {
+                sqlx::query!(
+                    "UPDATE users 
+                     SET total_drops = total_drops - ? 
+                     WHERE discord_id = ?",
+                    drop.quantity,
+                    discord_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
             
             // Remove the drop
             sqlx::query!(
@@ -121,25 +137,35 @@ pub async fn handle_drop_remove(
             // Log the drop removal
             logger::log_action(
                 ctx,
-                &discord_id,
+                discord_id,
                 "REMOVED DROP",
-                &format!("{}x {} ({}) [ID: {}]", drop.quantity, drop.item_name, format_gp(drop.value), drop.id)
+                &format!(
+                    "{}x {} ({}) [ID: {}]{}{}",
+                    drop.quantity,
+                    drop.item_name,
+                    format_gp(drop.value),
+                    drop.id,
+                    screenshots::describe(drop.screenshot_url.as_deref(), drop.screenshot_path.as_deref()),
+                    target.removed_by()
+                )
             ).await?;
             
+            // Deduct points from user, in the same transaction so the drop can't go without its points
+            if points_to_deduct > 0 {
+                ledger::record(
+                    &mut *tx,
+                    discord_id,
+                    &PointChange::new(-points_to_deduct, Reason::DropRemoved) // Negative to deduct points
+                        .source("drops", drop.id)
+                        .actor(&target.actor_id),
+                ).await?;
+            }
+            
             // Commit transaction
             tx.commit().await?;
             
-            // Deduct points from user
             if points_to_deduct > 0 {
-                let points_update = rank_manager::add_points(
-                    ctx,
-                    &discord_id,
-                    &command.member.as_ref()
-                        .and_then(|m| Some(m.display_name()))
-                        .unwrap_or(&command.user.name),
-                    -points_to_deduct, // Negative to deduct points
-                    db
-                ).await?;
+                let points_update = rank_manager::points_recorded(ctx, discord_id, &target.user_name, -points_to_deduct, db).await?;
                 
                 let message = if !points_update.crossed_ranks.is_empty() {
                     // User ranked down
@@ -176,19 +202,19 @@ pub async fn handle_drop_remove(
                 
                 command
                     .create_response(&ctx.http, CreateInteractionResponse::Message(
-                        CreateInteractionResponseMessage::new().content(message)
+                        CreateInteractionResponseMessage::new().content(target.reply(message))
                     ))
                     .await?;
             } else {
                 command
                     .create_response(&ctx.http, CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
-                            .content(format!(
+                            .content(target.reply(format!(
                                 "Drop removed: {}x {} ({}). No points were deducted.",
                                 drop.quantity,
                                 drop.item_name,
                                 format_gp(drop.value)
-                            ))
+                            )))
                     ))
                     .await?;
             }
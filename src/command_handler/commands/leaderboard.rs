@@ -1,19 +1,441 @@
 use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
 use serenity::all::{
+    ButtonStyle,
     CommandInteraction,
+    ComponentInteractionCollector,
+    CreateActionRow,
+    CreateButton,
     CreateInteractionResponse,
     CreateInteractionResponseMessage,
     CreateEmbed,
+    CreateEmbedFooter,
+    EditInteractionResponse,
     UserId,
 };
+use serenity::futures::StreamExt;
 use sqlx::SqlitePool;
 use crate::command_handler::{format_points, format_number, format_gp};
 
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const PAGE_SIZE: usize = 10;
+/// How long the page buttons keep working after the leaderboard is posted
+const PAGINATION_TIMEOUT_SECS: u64 = 300;
+
+const PREVIOUS_BUTTON: &str = "leaderboard_previous";
+const NEXT_BUTTON: &str = "leaderboard_next";
+const JUMP_BUTTON: &str = "leaderboard_jump";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Metric {
+    Points,
+    Gp,
+    Drops,
+    Clogs,
+    Pets,
+}
+
+impl Metric {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "points" => Some(Metric::Points),
+            "gp" => Some(Metric::Gp),
+            "drops" => Some(Metric::Drops),
+            "clogs" => Some(Metric::Clogs),
+            "pets" => Some(Metric::Pets),
+            _ => None,
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Metric::Points => "points",
+            Metric::Gp => "gp",
+            Metric::Drops => "drops",
+            Metric::Clogs => "clogs",
+            Metric::Pets => "pets",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Metric::Points => "Points",
+            Metric::Gp => "GP Value",
+            Metric::Drops => "Items Received",
+            Metric::Clogs => "Collection Log Count",
+            Metric::Pets => "Pet Count",
+        }
+    }
+
+    fn format(&self, value: i64) -> String {
+        match self {
+            Metric::Points => format_points(value),
+            Metric::Gp => format_gp(value),
+            Metric::Drops => format!("{} items", format_number(value)),
+            Metric::Clogs => format!("{} entries", format_number(value)),
+            Metric::Pets => format!("{} pets", format_number(value)),
+        }
+    }
+}
+
+/// Everything the options on `/leaderboard` can narrow the ranking down to
+struct LeaderboardFilter {
+    metric: Metric,
+    /// Human readable description of the time range, e.g. "This Week"
+    scope: String,
+    /// Inclusive lower and exclusive upper bounds on the entry timestamps
+    since: Option<String>,
+    until: Option<String>,
+    item: Option<String>,
+    source: Option<String>,
+    category: Option<String>,
+}
+
+impl LeaderboardFilter {
+    fn from_command(command: &CommandInteraction) -> Result<Self, String> {
+        let option = |name: &str| {
+            command.data.options
+                .iter()
+                .find(|opt| opt.name == name)
+                .and_then(|opt| opt.value.as_str())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+
+        let metric = match option("metric") {
+            Some(value) => Metric::parse(&value).ok_or_else(|| format!("Unknown metric '{}'.", value))?,
+            None => Metric::Points,
+        };
+
+        let from = option("from").map(|value| parse_date(&value)).transpose()?;
+        let to = option("to").map(|value| parse_date(&value)).transpose()?;
+
+        // Giving either date implies a custom range
+        let scope_name = option("scope").unwrap_or_else(|| {
+            if from.is_some() || to.is_some() { "custom" } else { "all_time" }.to_string()
+        });
+
+        let now = Utc::now().naive_utc();
+        let (scope, since, until) = match scope_name.as_str() {
+            "all_time" => ("All Time".to_string(), None, None),
+            "week" => ("Past 7 Days".to_string(), Some((now - Duration::days(7)).format(TIMESTAMP_FORMAT).to_string()), None),
+            "month" => ("Past 30 Days".to_string(), Some((now - Duration::days(30)).format(TIMESTAMP_FORMAT).to_string()), None),
+            "custom" => {
+                if from.is_none() && to.is_none() {
+                    return Err("A custom range needs a `from` and/or `to` date (YYYY-MM-DD).".to_string());
+                }
+                if let (Some(from), Some(to)) = (from, to) {
+                    if from > to {
+                        return Err("The `from` date must not be after the `to` date.".to_string());
+                    }
+                }
+                let scope = match (from, to) {
+                    (Some(from), Some(to)) => format!("{} to {}", from, to),
+                    (Some(from), None) => format!("Since {}", from),
+                    (None, Some(to)) => format!("Until {}", to),
+                    (None, None) => unreachable!(),
+                };
+                (
+                    scope,
+                    from.map(|date| date.and_hms_opt(0, 0, 0).unwrap().format(TIMESTAMP_FORMAT).to_string()),
+                    // `to` is inclusive, so stop at the start of the following day
+                    to.map(|date| (date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap().format(TIMESTAMP_FORMAT).to_string()),
+                )
+            }
+            other => return Err(format!("Unknown scope '{}'.", other)),
+        };
+
+        Ok(Self {
+            metric,
+            scope,
+            since,
+            until,
+            item: option("item"),
+            source: option("source"),
+            category: option("category"),
+        })
+    }
+
+    fn is_unfiltered(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+            && self.item.is_none() && self.source.is_none() && self.category.is_none()
+    }
+
+    fn title(&self) -> String {
+        let mut parts = vec![self.scope.clone()];
+        if let Some(item) = &self.item {
+            parts.push(format!("Item: {}", item));
+        }
+        if let Some(source) = &self.source {
+            parts.push(format!("Source: {}", source));
+        }
+        if let Some(category) = &self.category {
+            parts.push(format!("Category: {}", category));
+        }
+        format!("🏆 Top by {} ({})", self.metric.label(), parts.join(", "))
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a valid date, use YYYY-MM-DD.", value))
+}
+
+struct LeaderboardRow {
+    discord_id: String,
+    value: i64,
+}
+
+/// Ranks users by the chosen metric using only the drops, collection log entries and pets that match the filter
+async fn ranked_users(filter: &LeaderboardFilter, db: &SqlitePool) -> Result<Vec<LeaderboardRow>> {
+    // The all-time points ranking uses the stored totals so it agrees with /stats
+    let use_stored_points = filter.is_unfiltered();
+    let metric = filter.metric.key();
+
+    let rows = sqlx::query!(
+        r#"WITH category_items AS (
+            SELECT cli.item_name
+            FROM collection_log_items cli
+            JOIN v_categories_clogs vc ON vc.item_id = cli.item_id
+            WHERE vc.category = ?5
+        ),
+        drop_totals AS (
+            SELECT discord_id,
+                   SUM(points) AS points,
+                   SUM(value) AS gp,
+                   SUM(CASE WHEN counted THEN quantity ELSE 0 END) AS drop_count
+            FROM drops
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+              AND (?2 IS NULL OR timestamp < ?2)
+              AND (?3 IS NULL OR item_name = ?3)
+              AND (?4 IS NULL OR source = ?4)
+              AND (?5 IS NULL OR item_name IN (SELECT item_name FROM category_items))
+            GROUP BY discord_id
+        ),
+        clog_totals AS (
+            SELECT discord_id,
+                   SUM(points) AS points,
+                   COUNT(*) AS clog_count
+            FROM collection_log_entries
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+              AND (?2 IS NULL OR timestamp < ?2)
+              AND (?3 IS NULL OR item_name = ?3)
+              AND ?4 IS NULL
+              AND (?5 IS NULL OR item_name IN (SELECT item_name FROM category_items))
+            GROUP BY discord_id
+        ),
+        pet_totals AS (
+            SELECT discord_id,
+                   SUM(points) AS points,
+                   SUM(duplicate = 0) AS pet_count
+            FROM pets
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+              AND (?2 IS NULL OR timestamp < ?2)
+              AND (?3 IS NULL OR pet_name = ?3)
+              AND ?4 IS NULL
+              AND (?5 IS NULL OR pet_name IN (SELECT item_name FROM category_items))
+            GROUP BY discord_id
+        ),
+        totals AS (
+            SELECT u.discord_id,
+                   CASE WHEN ?6 THEN u.points
+                        ELSE COALESCE(d.points, 0) + COALESCE(c.points, 0) + COALESCE(p.points, 0) END AS points,
+                   COALESCE(d.gp, 0) AS gp,
+                   COALESCE(d.drop_count, 0) AS drop_count,
+                   COALESCE(c.clog_count, 0) AS clog_count,
+                   COALESCE(p.pet_count, 0) AS pet_count
+            FROM users u
+            LEFT JOIN drop_totals d ON d.discord_id = u.discord_id
+            LEFT JOIN clog_totals c ON c.discord_id = u.discord_id
+            LEFT JOIN pet_totals p ON p.discord_id = u.discord_id
+        ),
+        ranked AS (
+            SELECT discord_id,
+                   CASE ?7 WHEN 'gp' THEN gp
+                           WHEN 'drops' THEN drop_count
+                           WHEN 'clogs' THEN clog_count
+                           WHEN 'pets' THEN pet_count
+                           ELSE points END AS value
+            FROM totals
+        )
+        SELECT discord_id AS "discord_id!: String", value AS "value!: i64"
+        FROM ranked
+        WHERE value > 0
+        ORDER BY value DESC"#,
+        filter.since,
+        filter.until,
+        filter.item,
+        filter.source,
+        filter.category,
+        use_stored_points,
+        metric
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| LeaderboardRow { discord_id: row.discord_id, value: row.value })
+        .collect())
+}
+
+/// `/leaderboard` with any options: a single ranking for the chosen scope, metric and filters
+async fn handle_filtered_leaderboard(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let filter = match LeaderboardFilter::from_command(command) {
+        Ok(filter) => filter,
+        Err(message) => {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new().content(message)
+                ))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let entries: Vec<RankedEntry> = ranked_users(&filter, db).await?
+        .into_iter()
+        .map(|row| RankedEntry {
+            details: format!(" - {}\n", filter.metric.format(row.value)),
+            discord_id: row.discord_id,
+        })
+        .collect();
+
+    let title = filter.title();
+    send_paginated(command, ctx, &entries, |text, page, pages| {
+        CreateEmbed::new()
+            .title(&title)
+            .description(if text.is_empty() { "Nobody has anything matching these filters yet".to_string() } else { text })
+            .footer(CreateEmbedFooter::new(format!("Page {}/{}", page + 1, pages)))
+            .color(0xffd700)
+    })
+    .await
+}
+
+/// One line of a ranking; `details` is appended after the rank and user name
+struct RankedEntry {
+    discord_id: String,
+    details: String,
+}
+
+/// Formats one page of a ranking, looking up the user names for just that page
+async fn page_text(ctx: &serenity::prelude::Context, entries: &[RankedEntry], page: usize) -> Result<String> {
+    let mut text = String::new();
+    for (i, entry) in entries.iter().enumerate().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+        let user_name = user_name(ctx, &entry.discord_id).await?;
+        text.push_str(&format!("{}. **{}**{}", i + 1, user_name, entry.details));
+    }
+    Ok(text)
+}
+
+/// The user's Discord name, or the raw ID if the stored ID isn't a valid user ID
+async fn user_name(ctx: &serenity::prelude::Context, discord_id: &str) -> Result<String> {
+    match discord_id.parse::<u64>() {
+        Ok(id) if id != 0 => Ok(ctx.http.get_user(UserId::new(id)).await?.name),
+        _ => Ok(format!("Unknown user ({})", discord_id)),
+    }
+}
+
+fn page_buttons(page: usize, pages: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(PREVIOUS_BUTTON)
+            .label("Previous")
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new(NEXT_BUTTON)
+            .label("Next")
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= pages),
+        CreateButton::new(JUMP_BUTTON)
+            .label("Jump to me")
+            .style(ButtonStyle::Primary),
+    ])]
+}
+
+/// Posts the first page of a ranking and lets anyone page through it with the buttons until they time out.
+/// `build` turns a page's text, the page index and the page count into the embed to show.
+async fn send_paginated(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    entries: &[RankedEntry],
+    build: impl Fn(String, usize, usize) -> CreateEmbed,
+) -> Result<()> {
+    let pages = entries.len().div_ceil(PAGE_SIZE).max(1);
+    let mut page = 0;
+
+    let mut response = CreateInteractionResponseMessage::new()
+        .embed(build(page_text(ctx, entries, page).await?, page, pages));
+    if !entries.is_empty() {
+        response = response.components(page_buttons(page, pages));
+    }
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let message = command.get_response(&ctx.http).await?;
+    let mut interactions = ComponentInteractionCollector::new(ctx)
+        .message_id(message.id)
+        .timeout(std::time::Duration::from_secs(PAGINATION_TIMEOUT_SECS))
+        .stream();
+
+    while let Some(interaction) = interactions.next().await {
+        page = match interaction.data.custom_id.as_str() {
+            PREVIOUS_BUTTON => page.saturating_sub(1),
+            NEXT_BUTTON => (page + 1).min(pages - 1),
+            JUMP_BUTTON => {
+                let discord_id = interaction.user.id.to_string();
+                match entries.iter().position(|entry| entry.discord_id == discord_id) {
+                    Some(position) => position / PAGE_SIZE,
+                    None => {
+                        interaction
+                            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new()
+                                    .content("You're not on this leaderboard yet.")
+                                    .ephemeral(true)
+                            ))
+                            .await?;
+                        continue;
+                    }
+                }
+            }
+            _ => continue,
+        };
+
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .embed(build(page_text(ctx, entries, page).await?, page, pages))
+                    .components(page_buttons(page, pages))
+            ))
+            .await?;
+    }
+
+    // The collector has timed out, so take the buttons away rather than leave them dead
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().components(Vec::new()))
+        .await?;
+
+    Ok(())
+}
+
 pub async fn handle_leaderboard(
     command: &CommandInteraction,
     ctx: &serenity::prelude::Context,
     db: &SqlitePool,
 ) -> Result<()> {
+    if !command.data.options.is_empty() {
+        return handle_filtered_leaderboard(command, ctx, db).await;
+    }
+
     // Get all-time top users
     let top_users = sqlx::query!(
         r#"WITH user_clogs AS (
@@ -24,8 +446,7 @@ pub async fn handle_leaderboard(
         SELECT u.discord_id, u.points, u.total_drops, COALESCE(c.count, 0) as clog_count
         FROM users u
         LEFT JOIN user_clogs c ON u.discord_id = c.discord_id
-        ORDER BY u.points DESC
-        LIMIT 10"#
+        ORDER BY u.points DESC"#
     )
     .fetch_all(db)
     .await?;
@@ -98,31 +519,27 @@ pub async fn handle_leaderboard(
     .fetch_all(db)
     .await?;
 
-    // Format all-time leaderboard
-    let mut all_time = String::new();
-    for (i, user) in top_users.iter().enumerate() {
-        let discord_id = user.discord_id.clone().expect("Missing discord ID");
-        let user_id = UserId::new(discord_id.parse::<u64>().expect("Invalid discord ID"));
-        let user_name = ctx.http.get_user(user_id).await?.name;
-        all_time.push_str(&format!(
-            "{}. **{}**\n• Points: {}\n• Total Drops: {}\n• Collection Log: {}\n\n",
-            i + 1,
-            user_name,
-            format_points(user.points),
-            format_number(user.total_drops),
-            format_number(user.clog_count.into())
-        ));
-    }
+    // Format all-time leaderboard, the names are looked up a page at a time
+    let all_time: Vec<RankedEntry> = top_users
+        .iter()
+        .filter_map(|user| Some(RankedEntry {
+            discord_id: user.discord_id.clone()?,
+            details: format!(
+                "\n• Points: {}\n• Total Drops: {}\n• Collection Log: {}\n\n",
+                format_points(user.points),
+                format_number(user.total_drops),
+                format_number(user.clog_count.into())
+            ),
+        }))
+        .collect();
 
     // Format monthly droppers
     let mut monthly_drops = String::new();
     for (i, user) in top_droppers.iter().enumerate() {
-        let discord_id = user.discord_id.clone().expect("Missing discord ID");
-        let user_id = UserId::new(discord_id.parse::<u64>().expect("Invalid discord ID"));
-        let user_name = ctx.http.get_user(user_id).await?.name;
+        let user_name = user_name(ctx, user.discord_id.as_deref().unwrap_or_default()).await?;
         
         let best_drop = match &user.best_drop_name {
-            Some(name) => format!("\n• Best Drop: {} ({})", name, format_gp(user.best_drop_value.unwrap_or(0) as i64)),
+            Some(name) => format!("\n• Best Drop: {} ({})", name, format_gp(user.best_drop_value.unwrap_or(0))),
             None => String::new()
         };
         
@@ -130,8 +547,8 @@ pub async fn handle_leaderboard(
             "{}. **{}**\n• Drops: {}\n• Total Value: {}{}",
             i + 1,
             user_name,
-            format_number(user.drop_count.unwrap_or(0) as i64),
-            format_gp(user.total_value.unwrap_or(0) as i64),
+            format_number(user.drop_count.unwrap_or(0)),
+            format_gp(user.total_value.unwrap_or(0)),
             best_drop
         ));
         
@@ -146,12 +563,10 @@ pub async fn handle_leaderboard(
     // Format monthly collection loggers
     let mut monthly_clogs = String::new();
     for (i, user) in top_cloggers.iter().enumerate() {
-        let discord_id = user.discord_id.clone().expect("Missing discord ID");
-        let user_id = UserId::new(discord_id.parse::<u64>().expect("Invalid discord ID"));
-        let user_name = ctx.http.get_user(user_id).await?.name;
+        let user_name = user_name(ctx, user.discord_id.as_deref().unwrap_or_default()).await?;
         
         let best_entry = match &user.best_entry_name {
-            Some(name) => format!("\n• Best Entry: {} ({})", name, format_points(user.best_entry_points.unwrap_or(0).into())),
+            Some(name) => format!("\n• Best Entry: {} ({})", name, format_points(user.best_entry_points.unwrap_or(0))),
             None => String::new()
         };
         
@@ -159,8 +574,8 @@ pub async fn handle_leaderboard(
             "{}. **{}**\n• Entries: {}\n• Points: {}{}",
             i + 1,
             user_name,
-            format_number(user.entry_count.unwrap_or(0).into()),
-            format_points(user.total_points.unwrap_or(0).into()),
+            format_number(user.entry_count.unwrap_or(0)),
+            format_points(user.total_points.unwrap_or(0)),
             best_entry
         ));
         
@@ -172,19 +587,17 @@ pub async fn handle_leaderboard(
         monthly_clogs = "No collection log entries in the past 30 days".to_string();
     }
 
-    let embed = CreateEmbed::new()
-        .title("🏆 Leaderboards")
-        .field("All-Time Top 10", all_time, false)
-        .field("📅 Top Droppers (30 Days)", monthly_drops, true)
-        .field("📅 Top Collection Loggers (30 Days)", monthly_clogs, true)
-        .color(0xffd700);
-
-    command
-        .create_response(&ctx.http, CreateInteractionResponse::Message(
-            CreateInteractionResponseMessage::new()
-                .embed(embed)
-        ))
-        .await?;
-
-    Ok(())
+    send_paginated(command, ctx, &all_time, |text, page, pages| {
+        CreateEmbed::new()
+            .title("🏆 Leaderboards")
+            .field(
+                format!("All-Time Rankings (Page {}/{})", page + 1, pages),
+                if text.is_empty() { "No points recorded yet".to_string() } else { text },
+                false
+            )
+            .field("📅 Top Droppers (30 Days)", &monthly_drops, true)
+            .field("📅 Top Collection Loggers (30 Days)", &monthly_clogs, true)
+            .color(0xffd700)
+    })
+    .await
 } 
\ No newline at end of file
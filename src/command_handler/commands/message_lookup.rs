@@ -0,0 +1,120 @@
+use anyhow::Result;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use sqlx::SqlitePool;
+use crate::command_handler::{format_gp, format_number};
+
+/// ADMIN: What the tracker made of a RuneLite message and the rows it produced
+pub async fn handle_message_lookup(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let message = command.data.options
+        .iter()
+        .find(|opt| opt.name == "message")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Message not provided"))?;
+
+    // A message link ends in the message ID
+    let message_id = message.trim().trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+
+    let processed = sqlx::query!(
+        "SELECT status, parser, events, processed_at FROM processed_messages WHERE message_id = ?",
+        message_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    let Some(processed) = processed else {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("Message {} hasn't been processed, `/backfill` can pick it up if it's in the RuneLite channel.", message_id))
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    };
+
+    // Rows removed since (e.g. with /drop_remove) are still listed, without their details
+    let rows = sqlx::query!(
+        r#"SELECT r.source_table AS "source_table!: String",
+                  r.source_id AS "source_id!: i64",
+                  COALESCE(d.discord_id, c.discord_id, p.discord_id) AS "discord_id: String",
+                  COALESCE(d.item_name, c.item_name, p.pet_name, u.item_name) AS "item_name: String",
+                  d.quantity AS "quantity: i64",
+                  d.value AS "value: i64",
+                  COALESCE(d.points, c.points, p.points) AS "points: i64",
+                  u.rs_name AS "rs_name: String",
+                  u.status AS "unlinked_status: String"
+           FROM processed_message_rows r
+           LEFT JOIN drops d ON r.source_table = 'drops' AND d.id = r.source_id
+           LEFT JOIN collection_log_entries c ON r.source_table = 'collection_log_entries' AND c.id = r.source_id
+           LEFT JOIN pets p ON r.source_table = 'pets' AND p.id = r.source_id
+           LEFT JOIN unlinked_events u ON r.source_table = 'unlinked_events' AND u.id = r.source_id
+           WHERE r.message_id = ?
+           ORDER BY r.source_table, r.source_id"#,
+        message_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut text = format!(
+        "**Message {}** ({}, {})\nParser: {}\n",
+        message_id,
+        processed.status,
+        processed.processed_at.map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string()).unwrap_or_default(),
+        processed.parser.as_deref().unwrap_or("none, the message wasn't recognised")
+    );
+    if let Some(events) = &processed.events {
+        text += format!("Events: `{}`\n", events).as_str();
+    }
+
+    if rows.is_empty() {
+        text += "Nothing was recorded from it.";
+    } else {
+        text += "Recorded:\n";
+        for row in &rows {
+            let item_name = row.item_name.as_deref().unwrap_or("Unknown pet");
+            let details = match (row.source_table.as_str(), &row.discord_id, &row.rs_name) {
+                ("unlinked_events", _, Some(rs_name)) => format!(
+                    "{} for unlinked RS name '{}' ({})",
+                    item_name,
+                    rs_name,
+                    row.unlinked_status.as_deref().unwrap_or("pending")
+                ),
+                (_, Some(discord_id), _) => {
+                    let mut details = format!("<@{}> ", discord_id);
+                    if let (Some(quantity), Some(value)) = (row.quantity, row.value) {
+                        details += format!("{}x {} ({})", format_number(quantity), item_name, format_gp(value)).as_str();
+                    } else {
+                        details += item_name;
+                    }
+                    details += format!(", +{} points", format_number(row.points.unwrap_or(0))).as_str();
+                    details
+                }
+                _ => "removed since".to_string(),
+            };
+            text += format!("• {} #{}: {}\n", row.source_table, row.source_id, details).as_str();
+        }
+    }
+
+    // Discord messages are capped at 2000 characters, the events JSON is the first thing to go
+    if text.chars().count() > 2000 {
+        text = text.chars().take(1990).collect::<String>() + "...";
+    }
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(text)
+                .ephemeral(true)
+        ))
+        .await?;
+
+    Ok(())
+}
@@ -1,25 +1,46 @@
+pub mod backfill;
 pub mod drop;
 pub mod clog;
 pub mod points;
+pub mod points_history;
+pub mod points_adjust;
 pub mod leaderboard;
+pub mod message_lookup;
 pub mod stats;
 pub mod drop_remove;
 pub mod clog_remove;
+pub mod pet;
 pub mod rsname;
 pub mod rsname_remove;
 pub mod rsnames;
 pub mod moderation;
+pub mod price;
+pub mod scoring;
+pub mod submissions;
+pub mod target;
+pub mod unlinked;
 
 pub use drop::handle_drop;
 pub use clog::handle_clog;
 pub use points::handle_points;
+pub use points_history::handle_points_history;
+pub use points_adjust::handle_points_adjust;
 pub use leaderboard::handle_leaderboard;
 pub use stats::handle_stats;
 pub use drop_remove::handle_drop_remove;
 pub use clog_remove::handle_clog_remove;
+pub use pet::handle_pet;
 pub use rsname::handle_rsname;
 pub use rsname_remove::handle_rsname_remove;
 pub use rsnames::handle_rsnames; 
+pub use price::handle_price;
+pub use scoring::{handle_scoring, handle_scoring_set, handle_scoring_override};
+pub use submissions::handle_submission_review;
+pub use unlinked::handle_unlinked;
+pub use backfill::handle_backfill;
+pub use message_lookup::handle_message_lookup;
 pub use moderation::handle_recalculate;
+pub use moderation::handle_recalculate_undo;
 pub use moderation::handle_clamp;
-pub use moderation::handle_whitelist;
\ No newline at end of file
+pub use moderation::handle_whitelist;
+pub use moderation::handle_rank_sync;
\ No newline at end of file
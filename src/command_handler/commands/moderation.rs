@@ -1,24 +1,37 @@
 use std::collections::HashMap;
-use std::ops::Index;
 
 use anyhow::Result;
 use serenity::all::{
+    ButtonStyle,
     CommandInteraction,
+    ComponentInteractionCollector,
+    CreateActionRow,
+    CreateAttachment,
+    CreateButton,
     CreateInteractionResponse,
     CreateInteractionResponseMessage,
     EditInteractionResponse,
+    UserId,
 };
+use serenity::futures::StreamExt;
 use sqlx::SqlitePool;
 use crate::command_handler::CollectionLogManagerKey;
+use crate::config::ConfigKey;
 use crate::rank_manager;
 use crate::logger;
 use crate::runescape_tracker::RunescapeTrackerKey;
+use crate::scoring::ScoringRules;
+use crate::ledger::{self, PointChange, Reason};
 use sqlx::{QueryBuilder, Row, Sqlite};
 
+const CONFIRM_BUTTON: &str = "recalculate_confirm";
+const CANCEL_BUTTON: &str = "recalculate_cancel";
+/// How long a dry run waits for Confirm/Cancel before it's dropped
+const CONFIRM_TIMEOUT_SECS: u64 = 300;
+
 pub struct ItemData {
     item_id: i64,
     item_name: String,
-    percentage: f64,
     clamp: bool,
     old_points: i64,
     points: i64,
@@ -38,217 +51,519 @@ pub struct PlayerStats {
     name: String,
 }
 
-pub async fn handle_recalculate( //Big red button
-    command: &CommandInteraction,
+/// A clog entry whose points the recalculation will change
+struct ClogUpdate {
+    entry_id: i64,
+    discord_id: String,
+    old_points: i64,
+    new_points: i64,
+}
+
+/// Everything a recalculation would change, worked out without writing anything
+struct RecalculationPlan {
+    items: Vec<ItemData>,
+    updates: Vec<ClogUpdate>,
+}
+
+/// Sums the point changes in `updates` per member
+fn player_deltas<'a>(updates: impl IntoIterator<Item = &'a ClogUpdate>) -> HashMap<String, i64> {
+    let mut deltas: HashMap<String, i64> = HashMap::new();
+    for update in updates {
+        *deltas.entry(update.discord_id.clone()).or_default() += update.new_points - update.old_points;
+    }
+    deltas
+}
+
+async fn plan_recalculation(
     ctx: &serenity::prelude::Context,
     db: &SqlitePool,
-) -> Result<()> {
-    command
-        .create_response(&ctx.http, CreateInteractionResponse::Message(
-            CreateInteractionResponseMessage::new()
-                .content("Recalculating...")
-        ))
-        .await?;
+) -> Result<RecalculationPlan> {
     let data = ctx.data.read().await;
+    let clamp_cap = ScoringRules::load(db).await?.clamp_cap;
     //This query assumes:
     //Item should have a non-zero amount of clogs for us to care about it
     //Clamps may have been removed or added, and we want to fix any problem clogs
+    //Caps may have been raised or lowered, so clamped items not sitting at their cap are checked too
     //Whitelists may have been removed or added, same reason
     //Only low completion percentage clogs are an issue, so we check all those (a clamp may have been removed instead of adding to whitelist)
     let item_records = sqlx::query!(
         //I have to list every column to remove type inferrence issues ughhhhhhhhh
         "SELECT item_id, item_name, preferred_name, categories, percentage, highest_points as 'highest_points!: i64', whitelist, clog_count, clamp, clamped_category from v_item_data
-        WHERE clog_count > 0 AND ((clamp = 1 AND highest_points > 3000)
+        WHERE clog_count > 0 AND ((clamp = 1 AND highest_points != COALESCE(clamp_cap, ?))
         OR whitelist = 1 OR percentage < 10)
-        GROUP BY item_name ORDER BY percentage" //Until we work off item_id we gotta take care of dupes. Assume it's the most acquired one
+        GROUP BY item_name ORDER BY percentage", //Until we work off item_id we gotta take care of dupes. Assume it's the most acquired one
+        clamp_cap
     )
     .fetch_all(db)
     .await?;
-    let mut running_count = 0;
 
     tracing::info!("Found {} relevant recalculation records", item_records.len());
-    if item_records.len() > 0 {
 
-        let mut item_vector: Vec<ItemData> = vec![];
+    let mut plan = RecalculationPlan { items: vec![], updates: vec![] };
+    if item_records.is_empty() {
+        return Ok(plan);
+    }
 
-        let clog_manager = data.get::<CollectionLogManagerKey>().unwrap();
-        let rs_manager = data.get::<RunescapeTrackerKey>().unwrap();
+    let clog_manager = data.get::<CollectionLogManagerKey>().unwrap();
 
-        let mut clog_query: QueryBuilder<Sqlite> = QueryBuilder::new(
-            "SELECT * FROM collection_log_entries WHERE item_name IN (",
-        );
+    let mut clog_query: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT * FROM collection_log_entries WHERE item_name IN (",
+    );
 
-        let mut clog_query_separated = clog_query.separated(", ");
-        for (i, record) in item_records.iter().enumerate() {
-
-            clog_query_separated.push(format!("\"{}\"", record.item_name.clone().unwrap()));
-
-            let old_points: i64 = record.highest_points;
-            item_vector.push(ItemData {
-                item_id: record.item_id,
-                item_name: record.item_name.clone().unwrap(),
-                percentage: record.percentage.clone().unwrap().parse::<f64>().unwrap(),
-                clamp: if record.clamp == 1 && record.whitelist == Some(0) { true } else { false },
-                old_points: old_points,
-                points: clog_manager.calculate_points(record.item_name.clone().unwrap().as_str()).await.unwrap(),
-                affected: 0,
-            }); 
-        }
-        clog_query_separated.push_unseparated(")");
+    let mut clog_query_separated = clog_query.separated(", ");
+    for record in item_records.iter() {
 
-        let clog_records: Vec<ClogRow> = clog_query.build_query_as::<ClogRow>()
-        .fetch_all(db)
-        .await?;
+        clog_query_separated.push(format!("\"{}\"", record.item_name.clone().unwrap()));
 
-        tracing::info!("Found {} relevant clog records", clog_records.len());
+        let old_points: i64 = record.highest_points;
+        plan.items.push(ItemData {
+            item_id: record.item_id,
+            item_name: record.item_name.clone().unwrap(),
+            clamp: record.clamp == 1 && record.whitelist == Some(0),
+            old_points,
+            points: clog_manager.calculate_points(record.item_name.clone().unwrap().as_str()).await.unwrap(),
+            affected: 0,
+        });
+    }
+    clog_query_separated.push_unseparated(")");
 
-        if clog_records.len() > 0 {
+    let clog_records: Vec<ClogRow> = clog_query.build_query_as::<ClogRow>()
+    .fetch_all(db)
+    .await?;
 
-            let mut affected_player_deltas: HashMap<String, i64> = HashMap::new();
-            let mut pending_clog_updates: Vec<(i64, i64)> = Vec::new(); // (entry_id, new_points)
+    tracing::info!("Found {} relevant clog records", clog_records.len());
 
-            
+    for row in clog_records.into_iter() {
+        let target_item = plan.items.iter().position(|item| item.item_name == row.item_name).unwrap();
 
-            for (i, row) in clog_records.into_iter().enumerate() {
-                let target_item = item_vector.iter().position(|item| item.item_name == row.item_name).unwrap();
+        let point_delta = plan.items[target_item].points - row.points; //Positive if new number bigger, negative otherwise
 
-                let point_delta = item_vector[target_item].points - row.points; //Positive if new number bigger, negative otherwise
+        //Only lower points if we're clamping it. We don't want to lower points if we don't have to
+        //Raising is always fine, for clamped items that means their category's cap went up
+        if ((point_delta.is_negative()) && plan.items[target_item].clamp) || point_delta.is_positive() {
+            tracing::info!("User {} point change from {}: {}", row.discord_id, plan.items[target_item].item_name, point_delta);
 
-                let discord_id = row.discord_id;
+            plan.updates.push(ClogUpdate {
+                entry_id: row.id,
+                discord_id: row.discord_id,
+                old_points: row.points,
+                new_points: plan.items[target_item].points,
+            });
 
-                //Only lower points if we're clamping it. We don't want to lower points if we don't have to
-                if ((point_delta.is_negative()) && item_vector[target_item].clamp) || ((point_delta.is_positive()) && !item_vector[target_item].clamp) {
-                    running_count += 1;
+            plan.items[target_item].affected += 1;
+        }
+    }
 
-                    tracing::info!("User {} point change from {}: {}", discord_id, item_vector[target_item].item_name, point_delta);
+    tracing::info!("Total edited record count: {}", plan.updates.len());
 
-                    affected_player_deltas
-                        .entry(discord_id.clone())
-                        .and_modify(|player_delta| *player_delta += point_delta)
-                        .or_insert(point_delta);
+    Ok(plan)
+}
 
-                    pending_clog_updates.push((row.id, item_vector[target_item].points));
+/// The per-item part of the readout, shared by the dry run preview and the log message
+fn item_readout(items: &[ItemData]) -> String {
+    let mut readout = String::new();
+    for data in items.iter() {
+        if data.affected > 0 {
+            let point_delta = data.points - data.old_points;
+            readout += format!("\n**{}** ({}): from {} to {} points (**{}{}**), {} clogs affected",
+                data.item_name,
+                data.item_id,
+                data.old_points,
+                data.points,
+                if point_delta.is_positive() {"+"} else {""},
+                point_delta,
+                data.affected).as_str();
+        }
+    }
+    readout
+}
 
-                    item_vector[target_item].affected += 1;
-                }
-                
-            }
+fn player_readout(players: &HashMap<String, PlayerStats>) -> String {
+    let mut readout = String::from("\n**Affected users:**");
+    for player in players.iter() {
+        readout += format!("\n**{}** ({}): **{}{}** points",
+        player.1.name,
+        player.0,
+        if player.1.change.is_positive() {"+"} else {""},
+        player.1.change,
+        ).as_str();
+    }
+    readout
+}
 
-            tracing::info!("Total edited record count: {}", running_count);
+/// Looks up each member's RS name and new total after a commit, sends any rank up/down notifications
+/// and returns the stats for the readout. Done after the commit to keep API latency out of the transaction.
+async fn notify_affected_players(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    deltas: HashMap<String, i64>,
+) -> HashMap<String, PlayerStats> {
+    let data = ctx.data.read().await;
+    let rs_manager = data.get::<RunescapeTrackerKey>().unwrap();
+
+    let mut affected_players = HashMap::new();
+    for (discord_id, point_delta) in deltas.into_iter() {
+        let rs_name = rs_manager
+            .get_username_from_discord_id(ctx, discord_id.as_str())
+            .await
+            .unwrap_or_else(|_| "Unknown user".to_string());
+
+        let new_points = sqlx::query("SELECT points FROM users WHERE discord_id = ?")
+            .bind(discord_id.as_str())
+            .fetch_one(db)
+            .await
+            .map(|row| row.get::<i64, _>("points"))
+            .unwrap_or(0);
+        let old_points = new_points - point_delta;
+
+        if let Err(err) = rank_manager::notify_rank_transition(
+            ctx,
+            discord_id.as_str(),
+            &rs_name,
+            old_points,
+            new_points,
+            db,
+        )
+        .await
+        {
+            tracing::error!(
+                "Failed to emit rank transition notification for {}: {:?}",
+                discord_id,
+                err
+            );
+        }
 
-            if running_count > 0 {
-                let mut tx = db.begin().await?;
+        affected_players.insert(
+            discord_id,
+            PlayerStats {
+                change: point_delta,
+                name: rs_name,
+            },
+        );
+    }
+    affected_players
+}
 
-                for (entry_id, new_points) in &pending_clog_updates {
-                    sqlx::query("UPDATE collection_log_entries SET points = ? WHERE id = ?")
-                        .bind(*new_points)
-                        .bind(*entry_id)
-                        .execute(&mut *tx)
-                        .await?;
-                }
+/// What `apply_recalculation` did
+struct AppliedRecalculation {
+    batch_id: i64,
+    /// Entries whose points changed between planning and applying, which were left alone
+    skipped: usize,
+}
 
-                for (discord_id, point_delta) in &affected_player_deltas {
-                    // Ensure user row exists before applying the delta.
-                    sqlx::query(
-                        "INSERT INTO users (discord_id, points, total_drops) VALUES (?, 0, 0)
-                         ON CONFLICT(discord_id) DO NOTHING",
-                    )
-                    .bind(discord_id)
-                    .execute(&mut *tx)
-                    .await?;
-
-                    sqlx::query("UPDATE users SET points = points + ? WHERE discord_id = ?")
-                        .bind(*point_delta)
-                        .bind(discord_id)
-                        .execute(&mut *tx)
-                        .await?;
-                }
+/// Writes the plan in one transaction as a new recalculation batch, then notifies rank changes and logs the results.
+/// Entries whose points changed since the plan was made are left alone.
+async fn apply_recalculation(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    plan: RecalculationPlan,
+) -> Result<AppliedRecalculation> {
+    let commanding_officer_id = command.user.id.to_string();
+    let mut applied = Vec::new();
+    let mut tx = db.begin().await?;
 
-                tx.commit().await?;
-
-                // Post-commit lookups keep the transaction short and avoid external/API latency in tx.
-                let mut affected_players = HashMap::new();
-                for (discord_id, point_delta) in affected_player_deltas.into_iter() {
-                    let rs_name = rs_manager
-                        .get_username_from_discord_id(ctx, discord_id.as_str())
-                        .await
-                        .unwrap_or_else(|_| "Unknown user".to_string());
-
-                    let new_points = sqlx::query("SELECT points FROM users WHERE discord_id = ?")
-                        .bind(discord_id.as_str())
-                        .fetch_one(db)
-                        .await
-                        .map(|row| row.get::<i64, _>("points"))
-                        .unwrap_or(0);
-                    let old_points = new_points - point_delta;
-
-                    if let Err(err) = rank_manager::notify_rank_transition(
-                        ctx,
-                        discord_id.as_str(),
-                        &rs_name,
-                        old_points,
-                        new_points,
-                        db,
-                    )
-                    .await
-                    {
-                        tracing::error!(
-                            "Failed to emit rank transition notification for {}: {:?}",
-                            discord_id,
-                            err
-                        );
-                    }
-
-                    affected_players.insert(
-                        discord_id,
-                        PlayerStats {
-                            change: point_delta,
-                            name: rs_name,
-                        },
-                    );
-                }
+    let batch_id = sqlx::query!(
+        "INSERT INTO recalculation_batches (actor_id) VALUES (?)",
+        commanding_officer_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    // One ledger line per changed entry, so each member's change can be traced to the items
+    for update in plan.updates.iter() {
+        let changed = sqlx::query("UPDATE collection_log_entries SET points = ? WHERE id = ? AND points = ?")
+            .bind(update.new_points)
+            .bind(update.entry_id)
+            .bind(update.old_points)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if changed == 0 {
+            tracing::warn!("Clog entry {} changed since the recalculation was planned, skipping it", update.entry_id);
+            continue;
+        }
 
-                let mut info_readout = format!("\n**Recalculation Results** (only highest points previously awarded listed):\n{} total records affected!", running_count);
-
-                for (i, data) in item_vector.into_iter().enumerate() {
-                    if data.affected > 0 {
-                        let point_delta = data.points - data.old_points;
-                        info_readout += format!("\n**{}** ({}): from {} to {} points (**{}{}**), {} clogs affected",
-                            data.item_name,
-                            data.item_id,
-                            data.old_points,
-                            data.points,
-                            if point_delta.is_positive() {"+"} else {""},
-                            point_delta,
-                            data.affected).as_str();
-                    }
-                    
-                }
+        sqlx::query!(
+            "INSERT INTO recalculation_changes (batch_id, entry_id, discord_id, old_points, new_points) VALUES (?, ?, ?, ?, ?)",
+            batch_id,
+            update.entry_id,
+            update.discord_id,
+            update.old_points,
+            update.new_points
+        )
+        .execute(&mut *tx)
+        .await?;
 
-                info_readout += "\n**Affected users:**";
+        ledger::record(
+            &mut *tx,
+            &update.discord_id,
+            &PointChange::new(update.new_points - update.old_points, Reason::Recalculation)
+                .source("collection_log_entries", update.entry_id)
+                .actor(&commanding_officer_id),
+        )
+        .await?;
+        applied.push(update);
+    }
 
-                for (i, player) in affected_players.into_iter().enumerate() {
-                    info_readout += format!("\n**{}** ({}): **{}{}** points",
-                    player.1.name,
-                    player.0,
-                    if player.1.change.is_positive() {"+"} else {""},
-                    player.1.change,
-                    ).as_str();
-                }
-                command
-                    .edit_response(&ctx.http, EditInteractionResponse::new().content("Recalculation Complete!"))
-                    .await?;
-                let commanding_officer_id = command.user.id.to_string();
-                logger::log_action(ctx, &commanding_officer_id, "recalculate", &info_readout).await?;
-                
-            }
+    tx.commit().await?;
+
+    let skipped = plan.updates.len() - applied.len();
+    let affected_players = notify_affected_players(ctx, db, player_deltas(applied.iter().copied())).await;
+
+    let mut info_readout = format!("\n**Recalculation Results** (batch #{}, only highest points previously awarded listed):\n{} total records affected!", batch_id, applied.len());
+    if skipped > 0 {
+        info_readout += format!("\n{} records changed after the dry run and were skipped.", skipped).as_str();
+    }
+    info_readout += &item_readout(&plan.items);
+    info_readout += &player_readout(&affected_players);
+
+    logger::log_action(ctx, &commanding_officer_id, "recalculate", &info_readout).await?;
+    Ok(AppliedRecalculation { batch_id, skipped })
+}
+
+fn confirm_buttons() -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(CONFIRM_BUTTON)
+            .label("Confirm")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(CANCEL_BUTTON)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+/// Shows the admin what the plan would change and only applies it once they press Confirm
+async fn preview_recalculation(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    plan: RecalculationPlan,
+) -> Result<()> {
+    let deltas = player_deltas(plan.updates.iter());
+    let mut preview_players = HashMap::new();
+    {
+        let data = ctx.data.read().await;
+        let rs_manager = data.get::<RunescapeTrackerKey>().unwrap();
+        for (discord_id, change) in deltas.into_iter() {
+            let name = rs_manager
+                .get_username_from_discord_id(ctx, discord_id.as_str())
+                .await
+                .unwrap_or_else(|_| "Unknown user".to_string());
+            preview_players.insert(discord_id, PlayerStats { change, name });
         }
     }
-    if running_count == 0 {
+
+    let preview = format!(
+        "**Recalculation Preview** (nothing has been saved yet):\n{} total records would change!{}{}",
+        plan.updates.len(),
+        item_readout(&plan.items),
+        player_readout(&preview_players),
+    );
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!(
+                "Dry run: {} records for {} members would change. The full breakdown is attached, confirm within {} minutes to apply it.",
+                plan.updates.len(),
+                preview_players.len(),
+                CONFIRM_TIMEOUT_SECS / 60
+            ))
+            .new_attachment(CreateAttachment::bytes(preview.into_bytes(), "recalculation_preview.txt"))
+            .components(confirm_buttons()))
+        .await?;
+
+    let message = command.get_response(&ctx.http).await?;
+    let interaction = ComponentInteractionCollector::new(ctx)
+        .message_id(message.id)
+        .author_id(command.user.id)
+        .timeout(std::time::Duration::from_secs(CONFIRM_TIMEOUT_SECS))
+        .stream()
+        .next()
+        .await;
+
+    let Some(interaction) = interaction else {
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new()
+                .content("Dry run timed out, nothing was changed.")
+                .components(Vec::new()))
+            .await?;
+        return Ok(());
+    };
+
+    if interaction.data.custom_id != CONFIRM_BUTTON {
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content("Recalculation cancelled, nothing was changed.")
+                    .components(Vec::new())
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content("Recalculating...")
+                .components(Vec::new())
+        ))
+        .await?;
+
+    let applied = apply_recalculation(command, ctx, db, plan).await?;
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(completion_message(&applied)))
+        .await?;
+    Ok(())
+}
+
+fn completion_message(applied: &AppliedRecalculation) -> String {
+    let mut message = format!("Recalculation Complete! Saved as batch #{}, use `/recalculate_undo batch:{}` to revert it.", applied.batch_id, applied.batch_id);
+    if applied.skipped > 0 {
+        message += format!("\n{} records changed since the dry run and were skipped, run it again to pick them up.", applied.skipped).as_str();
+    }
+    message
+}
+
+pub async fn handle_recalculate( //Big red button
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let dry_run = command.data.options
+        .iter()
+        .find(|opt| opt.name == "dry_run")
+        .and_then(|opt| opt.value.as_bool())
+        .unwrap_or(false);
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(if dry_run {"Working out what would change..."} else {"Recalculating..."})
+        ))
+        .await?;
+
+    let plan = plan_recalculation(ctx, db).await?;
+    if plan.updates.is_empty() {
         command
             .edit_response(&ctx.http, EditInteractionResponse::new().content("Nothing to report, sheriff!"))
             .await?;
+        return Ok(());
+    }
+
+    if dry_run {
+        return preview_recalculation(command, ctx, db, plan).await;
+    }
+
+    let applied = apply_recalculation(command, ctx, db, plan).await?;
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(completion_message(&applied)))
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_recalculate_undo(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let batch_id = command.data.options
+        .iter()
+        .find(|opt| opt.name == "batch")
+        .and_then(|opt| opt.value.as_i64())
+        .ok_or_else(|| anyhow::anyhow!("Batch not provided"))?;
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(format!("Undoing recalculation #{}...", batch_id))
+        ))
+        .await?;
+
+    let batch = sqlx::query!(
+        "SELECT undone_at FROM recalculation_batches WHERE id = ?",
+        batch_id
+    )
+    .fetch_optional(db)
+    .await?;
+
+    let message = match batch {
+        None => Some(format!("There's no recalculation #{}.", batch_id)),
+        Some(batch) => batch.undone_at.map(|undone_at| format!("Recalculation #{} was already undone on {}.", batch_id, undone_at)),
+    };
+    if let Some(message) = message {
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new().content(message))
+            .await?;
+        return Ok(());
+    }
+
+    let changes = sqlx::query!(
+        "SELECT entry_id, discord_id, old_points, new_points FROM recalculation_changes WHERE batch_id = ?",
+        batch_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let commanding_officer_id = command.user.id.to_string();
+    let mut deltas: HashMap<String, i64> = HashMap::new();
+    let mut reverted = 0;
+    let mut tx = db.begin().await?;
+
+    for change in changes.iter() {
+        // Entries removed or re-scored since then are left as they are, their points already moved on
+        let changed = sqlx::query("UPDATE collection_log_entries SET points = ? WHERE id = ? AND points = ?")
+            .bind(change.old_points)
+            .bind(change.entry_id)
+            .bind(change.new_points)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if changed == 0 {
+            continue;
+        }
+
+        let point_delta = change.old_points - change.new_points;
+        ledger::record(
+            &mut *tx,
+            &change.discord_id,
+            &PointChange::new(point_delta, Reason::RecalculationUndo)
+                .source("collection_log_entries", change.entry_id)
+                .actor(&commanding_officer_id),
+        )
+        .await?;
+        *deltas.entry(change.discord_id.clone()).or_default() += point_delta;
+        reverted += 1;
+    }
+
+    sqlx::query!(
+        "UPDATE recalculation_batches SET undone_at = CURRENT_TIMESTAMP, undone_by = ? WHERE id = ?",
+        commanding_officer_id,
+        batch_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let skipped = changes.len() - reverted;
+    let affected_players = notify_affected_players(ctx, db, deltas).await;
+
+    let mut info_readout = format!("\n**Recalculation #{} Undone**\n{} records reverted!", batch_id, reverted);
+    if skipped > 0 {
+        info_readout += format!("\n{} records were removed or changed since and were left alone.", skipped).as_str();
+    }
+    info_readout += &player_readout(&affected_players);
+
+    let mut response = format!("Recalculation #{} undone! {} records reverted for {} members.", batch_id, reverted, affected_players.len());
+    if skipped > 0 {
+        response += format!(" {} records were removed or changed since and were left alone.", skipped).as_str();
     }
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(response))
+        .await?;
+    logger::log_action(ctx, &commanding_officer_id, "recalculate undo", &info_readout).await?;
     Ok(())
 }
 
@@ -265,14 +580,21 @@ pub async fn handle_clamp(
         .and_then(|opt| opt.value.as_str())
         .ok_or_else(|| anyhow::anyhow!("Category name not provided"))?;
 
+    // Only /clamp has max_points, unclamping clears the cap so clamping again starts from the default
+    let max_points = options
+        .iter()
+        .find(|opt| opt.name == "max_points")
+        .and_then(|opt| opt.value.as_i64());
+
     let one_or_zero = if on_or_off {1} else {0};
 
-    sqlx::query!("UPDATE category_table SET clamp=? WHERE category=?", one_or_zero, category_name)
+    sqlx::query!("UPDATE category_table SET clamp=?, clamp_cap=? WHERE category=?", one_or_zero, max_points, category_name)
     .execute(db).await?;
 
+    let clamp_cap = max_points.unwrap_or(ScoringRules::load(db).await?.clamp_cap as i64);
     let response_string = format!("{} is now {}", category_name, if one_or_zero == 1 {
-        "clamped! Items in this category will only give a maximum of 3000 points."
-    } else {"unclamped! Items in this category can go beyond 3000 points!"});
+        format!("clamped! Items in this category will only give a maximum of {} points.", clamp_cap)
+    } else {format!("unclamped! Items in this category can go beyond {} points!", clamp_cap)});
 
     command
                 .create_response(&ctx.http, CreateInteractionResponse::Message(
@@ -281,7 +603,8 @@ pub async fn handle_clamp(
                 ))
                 .await?;
     let commanding_officer_id = command.user.id.to_string();
-    logger::log_action(ctx, &commanding_officer_id, if one_or_zero == 1 {"CLAMPED"} else {"UNCLAMPED"}, &category_name).await?;
+    let details = if one_or_zero == 1 {format!("{} (max {} points)", category_name, clamp_cap)} else {category_name.to_string()};
+    logger::log_action(ctx, &commanding_officer_id, if one_or_zero == 1 {"CLAMPED"} else {"UNCLAMPED"}, &details).await?;
     Ok(())
 }
 
@@ -315,10 +638,125 @@ pub async fn handle_whitelist( //These are both basically the same function but
                 ))
                 .await?;
     let commanding_officer_id = command.user.id.to_string();
-    logger::log_action(ctx, &commanding_officer_id, if one_or_zero == 1 {"WHITELISTED"} else {"UNWHITELISTED"}, &item_name).await?;
+    logger::log_action(ctx, &commanding_officer_id, if one_or_zero == 1 {"WHITELISTED"} else {"UNWHITELISTED"}, item_name).await?;
     Ok(())
 }
 
+pub async fn handle_rank_sync(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("Syncing rank roles...")
+        ))
+        .await?;
+
+    let guild_id = {
+        let data = ctx.data.read().await;
+        data.get::<ConfigKey>().and_then(|config| config.guild_id)
+    };
+    let Some(guild_id) = guild_id else {
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new()
+                .content("Rank role management is disabled. Set `GUILD_ID` to enable it."))
+            .await?;
+        return Ok(());
+    };
+
+    let rank_roles = rank_manager::load_rank_roles(ctx, guild_id, db).await?;
+    if rank_roles.is_empty() {
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new()
+                .content("None of the ranks have a matching role in this server."))
+            .await?;
+        return Ok(());
+    }
+
+    let users = sqlx::query!("SELECT discord_id, points FROM users")
+        .fetch_all(db)
+        .await?;
+
+    let mut members_changed = 0;
+    let mut roles_added = 0;
+    let mut roles_removed = 0;
+    let mut not_in_guild = 0;
+    let mut failed = 0;
+    let mut changes = Vec::new();
+
+    for user in users {
+        let Some(discord_id) = user.discord_id else { continue };
+        let Ok(user_id) = discord_id.parse::<u64>().map(UserId::new) else { continue };
+
+        match rank_manager::sync_member_roles(ctx, guild_id, user_id, user.points, &rank_roles).await {
+            Ok(Some(sync)) => {
+                if !sync.added.is_empty() || !sync.removed.is_empty() {
+                    members_changed += 1;
+                    roles_added += sync.added.len();
+                    roles_removed += sync.removed.len();
+                    changes.push(format!("<@{}>: {}{}",
+                        discord_id,
+                        sync.added.iter().map(|r| format!("+{} ", r)).collect::<String>(),
+                        sync.removed.iter().map(|r| format!("-{} ", r)).collect::<String>()));
+                }
+                if !sync.failed.is_empty() {
+                    failed += 1;
+                    changes.push(format!("<@{}>: failed {}",
+                        discord_id,
+                        sync.failed.iter().map(|(role, why)| format!("{} ({})", role, why)).collect::<Vec<_>>().join(", ")));
+                }
+            }
+            Ok(None) => not_in_guild += 1,
+            Err(why) => {
+                tracing::error!("Failed to sync rank roles for {}: {:?}", discord_id, why);
+                failed += 1;
+            }
+        }
+    }
+
+    let summary = format!(
+        "Rank sync complete! {} members updated ({} roles added, {} removed), {} not in the server, {} failed.",
+        members_changed, roles_added, roles_removed, not_in_guild, failed
+    );
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(&summary))
+        .await?;
+    let commanding_officer_id = command.user.id.to_string();
+    logger::log_action(ctx, &commanding_officer_id, "RANK SYNC", &summary).await?;
+    // Every member changed is listed after the summary, split up so no log message goes over Discord's limit
+    for chunk in chunk_lines(&changes, LOG_CHUNK_LIMIT) {
+        logger::log_generic(ctx, &chunk).await?;
+    }
+    Ok(())
+}
+
+/// Room left in a 2000 character Discord message once the log's timestamp is added
+const LOG_CHUNK_LIMIT: usize = 1900;
+
+/// Joins the lines into as few messages of at most `limit` characters as it can, cutting any single
+/// line that's longer than that on its own
+fn chunk_lines(lines: &[String], limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for line in lines {
+        let line: String = line.chars().take(limit).collect();
+        if !chunk.is_empty() && chunk.chars().count() + 1 + line.chars().count() > limit {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        if !chunk.is_empty() {
+            chunk.push('\n');
+        }
+        chunk += &line;
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
 // async fn is_allowed( //I wrote this and then found out you can specify required perms
 //     command: &CommandInteraction,
 //     ctx: &serenity::prelude::Context,
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool};
+use crate::command_handler::{CollectionLogManagerKey, format_points, format_number};
+use crate::command_handler::commands::submissions::{self, Submission, SubmissionKind};
+use crate::command_handler::commands::target::Target;
+use crate::rank_manager::{self, PointsUpdate};
+use crate::logger;
+use crate::ledger::{self, PointChange, Reason};
+use crate::processed_messages;
+use crate::scoring::ScoringRules;
+use crate::screenshots::{self, Screenshot};
+
+pub async fn handle_pet(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let pet_name = command.data.options
+        .iter()
+        .find(|opt| opt.name == "pet")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Pet not provided"))?;
+
+    let target = Target::from_command(command);
+
+    let known_pet = {
+        let data = ctx.data.read().await;
+        let collection_log_manager = data.get::<CollectionLogManagerKey>()
+            .ok_or_else(|| anyhow::anyhow!("Collection log manager not found"))?;
+        collection_log_manager.is_pet(pet_name).await
+    };
+    if !known_pet {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("'{}' isn't a pet in the collection log.", pet_name))
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    if let Some(timestamp) = pet_logged_at(db, &target.discord_id, pet_name).await {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "You already logged {} on {}!",
+                        pet_name,
+                        timestamp.format("%B %d, %Y at %H:%M UTC")
+                    ))
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    let attachment = submissions::screenshot_attachment(command);
+    if submissions::approval_required(ctx).await {
+        let Some(attachment) = attachment else {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("Pets need a mod's approval, attach a `screenshot` of the pet and try again.")
+                        .ephemeral(true)
+                ))
+                .await?;
+            return Ok(());
+        };
+
+        return submissions::submit(command, ctx, db, &Submission {
+            kind: SubmissionKind::Pet,
+            item_name: pet_name,
+            quantity: 1,
+            value: None,
+            source: None,
+            dropped_at: None,
+            screenshot: &screenshots::archive(ctx, attachment).await,
+            shares: &[],
+        }).await;
+    }
+
+    let screenshot = match attachment {
+        Some(attachment) => screenshots::archive(ctx, attachment).await,
+        None => Screenshot::default(),
+    };
+
+    let (points, points_update) = record_pet(ctx, db, &NewPet {
+        discord_id: &target.discord_id,
+        user_name: &target.user_name,
+        pet_name: Some(pet_name),
+        duplicate: false,
+        screenshot: &screenshot,
+        actor_id: Some(&target.actor_id),
+        message_id: None,
+        when: None,
+    }).await?;
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(pet_recorded_message(Some(pet_name), false, points, &points_update))
+        ))
+        .await?;
+
+    Ok(())
+}
+
+/// When the member logged this pet, if they already have. Duplicates don't count, they're the same pet again
+pub async fn pet_logged_at<'e, E>(executor: E, discord_id: &str, pet_name: &str) -> Option<NaiveDateTime>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query!(
+        "SELECT timestamp FROM pets
+         WHERE discord_id = ? AND pet_name = ? AND duplicate = 0
+         ORDER BY timestamp
+         LIMIT 1",
+        discord_id,
+        pet_name
+    )
+    .fetch_optional(executor)
+    .await
+    .ok()
+    .flatten()
+    .and_then(|pet| pet.timestamp)
+}
+
+/// A pet ready to be recorded, from /pet, an approved submission or the RuneLite channel
+pub struct NewPet<'a> {
+    pub discord_id: &'a str,
+    pub user_name: &'a str,
+    /// `None` when the notification didn't say which pet it was
+    pub pet_name: Option<&'a str>,
+    pub duplicate: bool,
+    pub screenshot: &'a Screenshot,
+    /// Discord ID of whoever recorded it, `None` when it was auto-tracked
+    pub actor_id: Option<&'a str>,
+    /// The RuneLite message an auto-tracked pet came from
+    pub message_id: Option<&'a str>,
+    /// When the pet was received, `None` for now
+    pub when: Option<NaiveDateTime>,
+}
+
+/// Inserts the pet, logs it and awards its points. Returns the points it gave
+pub async fn record_pet(
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    pet: &NewPet<'_>,
+) -> Result<(i64, PointsUpdate)> {
+    let points = ScoringRules::load(db).await?.pet_points(pet.duplicate);
+
+    let mut tx = db.begin().await?;
+    let pet_id = insert_pet(&mut tx, pet, points).await?;
+    tx.commit().await?;
+
+    let action = if pet.actor_id.is_some() {"ADDED PET"} else {"AUTO-PET"};
+    logger::log_action(ctx, pet.discord_id, action, &log_details(pet, points, pet_id)).await?;
+
+    let points_update = rank_manager::points_recorded(ctx, pet.discord_id, pet.user_name, points, db).await?;
+
+    Ok((points, points_update))
+}
+
+/// Writes the pet row and its ledger entry, for callers that record it as part of a bigger transaction.
+/// Send the rank notifications with `rank_manager::points_recorded` once it's committed. Returns the pet's ID
+pub async fn insert_pet(conn: &mut SqliteConnection, pet: &NewPet<'_>, points: i64) -> Result<i64> {
+    // Insert or update user
+    sqlx::query!(
+        "INSERT INTO users (discord_id, points, total_drops)
+         VALUES (?, 0, 0)
+         ON CONFLICT(discord_id) DO NOTHING",
+        pet.discord_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let pet_id = sqlx::query!(
+        "INSERT INTO pets (discord_id, pet_name, duplicate, points, timestamp, screenshot_url, screenshot_path, message_id)
+         VALUES (?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?, ?)",
+        pet.discord_id,
+        pet.pet_name,
+        pet.duplicate,
+        points,
+        pet.when,
+        pet.screenshot.url,
+        pet.screenshot.path,
+        pet.message_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    if let Some(message_id) = pet.message_id {
+        processed_messages::link_row(&mut *conn, message_id, "pets", pet_id).await?;
+    }
+
+    ledger::record(
+        &mut *conn,
+        pet.discord_id,
+        &PointChange {
+            actor_id: pet.actor_id,
+            ..PointChange::new(points, Reason::Pet).source("pets", pet_id)
+        },
+    ).await?;
+
+    Ok(pet_id)
+}
+
+/// The bot log line for a recorded pet
+pub fn log_details(pet: &NewPet<'_>, points: i64, pet_id: i64) -> String {
+    let mut details = format!(
+        "{}{} (+{} points) [ID: {}]",
+        pet.pet_name.unwrap_or("Unknown pet"),
+        if pet.duplicate {" (duplicate)"} else {""},
+        format_number(points),
+        pet_id
+    );
+    if let Some(actor_id) = pet.actor_id.filter(|actor_id| *actor_id != pet.discord_id) {
+        details += format!(", recorded by <@{}>", actor_id).as_str();
+    }
+    details += &pet.screenshot.link();
+    details
+}
+
+/// The reply for a recorded pet, including any rank ups
+pub fn pet_recorded_message(pet_name: Option<&str>, duplicate: bool, points: i64, points_update: &PointsUpdate) -> String {
+    let pet = match (pet_name, duplicate) {
+        (Some(name), false) => format!("🐾 New pet: **{}**", name),
+        (Some(name), true) => format!("🐾 Duplicate pet: **{}**", name),
+        (None, false) => "🐾 New pet".to_string(),
+        (None, true) => "🐾 Duplicate pet".to_string(),
+    };
+
+    let mut message = format!("{} (+{} points)! You now have {}.", pet, format_number(points), format_points(points_update.new_points));
+    if !points_update.crossed_ranks.is_empty() {
+        message += format!("\n🎆 **RANK UP!** Reached {}", points_update.crossed_ranks.join(", ")).as_str();
+    }
+    message
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use sqlx::SqlitePool;
+use crate::command_handler::format_points;
+use crate::command_handler::commands::target::Target;
+use crate::rank_manager;
+use crate::logger;
+use crate::ledger::{PointChange, Reason};
+
+/// Longest reason accepted, it has to fit in the log and history listings
+pub const MAX_REASON_LENGTH: u16 = 150;
+
+pub async fn handle_points_adjust(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let options = &command.data.options;
+    let target = Target::from_command(command);
+
+    let amount = options
+        .iter()
+        .find(|opt| opt.name == "amount")
+        .and_then(|opt| opt.value.as_i64())
+        .ok_or_else(|| anyhow::anyhow!("Amount not provided"))?;
+
+    let reason = options
+        .iter()
+        .find(|opt| opt.name == "reason")
+        .and_then(|opt| opt.value.as_str())
+        .map(str::trim)
+        .unwrap_or_default();
+
+    if amount == 0 || reason.is_empty() {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("Give a non-zero `amount` and a `reason` for the adjustment.")
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    let adjustment_id = sqlx::query!(
+        "INSERT INTO point_adjustments (discord_id, delta, reason, actor_id) VALUES (?, ?, ?, ?)",
+        target.discord_id,
+        amount,
+        reason,
+        target.actor_id
+    )
+    .execute(db)
+    .await?
+    .last_insert_rowid();
+
+    let points_update = rank_manager::add_points(
+        ctx,
+        &target.discord_id,
+        &target.user_name,
+        PointChange::new(amount, Reason::Adjustment)
+            .source("point_adjustments", adjustment_id)
+            .actor(&target.actor_id),
+        db
+    ).await?;
+
+    let mut message = format!(
+        "<@{}> {} {} for {}. They now have {}.",
+        target.discord_id,
+        if amount > 0 {"was given"} else {"lost"},
+        format_points(amount.abs()),
+        reason,
+        format_points(points_update.new_points)
+    );
+    if !points_update.crossed_ranks.is_empty() {
+        message += format!(
+            "\n{} {}",
+            if amount > 0 {"🎆 **RANK UP!** Reached"} else {"⬇️ **RANK DOWN!** Lost"},
+            points_update.crossed_ranks.join(", ")
+        ).as_str();
+    }
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new().content(message)
+        ))
+        .await?;
+
+    logger::log_action(
+        ctx,
+        &target.discord_id,
+        "ADJUSTED POINTS",
+        &format!("{:+} for {} [ID: {}], by <@{}>", amount, reason, adjustment_id, target.actor_id),
+    ).await?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    CreateEmbed,
+};
+use sqlx::SqlitePool;
+use crate::command_handler::{format_points, format_number};
+use crate::ledger::describe_reason;
+
+/// How many of the latest ledger lines to list
+const RECENT_LIMIT: i64 = 15;
+/// Embed fields can't be longer than this
+const FIELD_LIMIT: usize = 1024;
+
+pub async fn handle_points_history(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let user_id = command.data.options
+        .iter()
+        .find(|opt| opt.name == "user")
+        .and_then(|opt| opt.value.as_user_id())
+        .unwrap_or(command.user.id);
+    let discord_id = user_id.to_string();
+    let user_name = user_id.to_user(&ctx.http).await?.name;
+
+    let total = sqlx::query!(
+        "SELECT points FROM users WHERE discord_id = ?",
+        discord_id
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|user| user.points)
+    .unwrap_or(0);
+
+    // Totals per reason add up to the member's points
+    let breakdown = sqlx::query!(
+        r#"SELECT reason, COUNT(*) AS "count!: i64", SUM(delta) AS "points!: i64"
+         FROM point_transactions
+         WHERE discord_id = ?
+         GROUP BY reason
+         ORDER BY SUM(delta) DESC"#,
+        discord_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let recent = sqlx::query!(
+        r#"SELECT t.id AS "id!: i64", t.delta, t.reason, t.source_table, t.source_id, t.actor_id,
+                t.timestamp AS "timestamp!: chrono::NaiveDateTime", a.reason AS "note?: String"
+         FROM point_transactions t
+         LEFT JOIN point_adjustments a ON t.source_table = 'point_adjustments' AND a.id = t.source_id
+         WHERE t.discord_id = ?
+         ORDER BY t.id DESC
+         LIMIT ?"#,
+        discord_id,
+        RECENT_LIMIT
+    )
+    .fetch_all(db)
+    .await?;
+
+    let breakdown_text = if breakdown.is_empty() {
+        "No point changes recorded yet".to_string()
+    } else {
+        breakdown
+            .iter()
+            .map(|row| format!(
+                "• {}: {:+} ({} changes)",
+                describe_reason(&row.reason),
+                row.points,
+                format_number(row.count)
+            ))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let recent_lines: Vec<String> = recent
+        .iter()
+        .map(|row| {
+            // Adjustments show the admin's reason rather than their row
+            let source = match (&row.note, &row.source_table, row.source_id) {
+                (Some(note), _, _) => format!(": {}", note),
+                (None, Some(table), Some(id)) => format!(" [{} #{}]", table, id),
+                _ => String::new(),
+            };
+            let actor = match &row.actor_id {
+                Some(actor_id) if *actor_id != discord_id => format!(" by <@{}>", actor_id),
+                _ => String::new(),
+            };
+            format!(
+                "`#{}` {} **{:+}** {}{}{}",
+                row.id,
+                row.timestamp.format("%Y-%m-%d"),
+                row.delta,
+                describe_reason(&row.reason),
+                source,
+                actor
+            )
+        })
+        .collect();
+
+    // Long adjustment reasons can push the list past the field limit, the oldest lines go first then
+    let mut shown = 0;
+    let mut length = 0;
+    for line in &recent_lines {
+        // Every line after the first also takes a line break
+        let needed = line.chars().count() + usize::from(shown > 0);
+        if length + needed > FIELD_LIMIT {
+            break;
+        }
+        length += needed;
+        shown += 1;
+    }
+    let (recent_title, recent_text) = if recent_lines.is_empty() {
+        ("Latest Changes".to_string(), "No point changes recorded yet".to_string())
+    } else {
+        (format!("Latest {} Changes", shown), recent_lines[..shown].join("\n"))
+    };
+
+    let embed = CreateEmbed::new()
+        .title(format!("{}'s Points History", user_name))
+        .color(0x00ff00)
+        .description(format!("Total: **{}**", format_points(total)))
+        .field("Breakdown", breakdown_text, false)
+        .field(recent_title, recent_text, false);
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+        ))
+        .await?;
+
+    Ok(())
+}
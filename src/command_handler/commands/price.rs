@@ -0,0 +1,78 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    CreateEmbed,
+};
+use crate::command_handler::{PriceManagerKey, format_gp};
+use crate::prices::PriceManager;
+
+const WIKI_IMAGE_URL: &str = "https://oldschool.runescape.wiki/images/";
+
+pub async fn handle_price(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+) -> Result<()> {
+    let item_name = command.data.options
+        .iter()
+        .find(|opt| opt.name == "item")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Item name not provided"))?;
+
+    let data = ctx.data.read().await;
+    let price_manager = data.get::<PriceManagerKey>()
+        .ok_or_else(|| anyhow::anyhow!("Price manager not found"))?;
+
+    let Some((mapping, price)) = price_manager.get_latest_price(item_name).await else {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("Item '{}' not found in price database.", item_name))
+            ))
+            .await?;
+        return Ok(());
+    };
+
+    let value = price.value(&mapping);
+    let optional_gp = |price: Option<i64>| price.map(format_gp).unwrap_or_else(|| "Unknown".to_string());
+
+    let mut embed = CreateEmbed::new()
+        .title(&mapping.name)
+        .color(0xffd700)
+        .field("Instant Buy", optional_gp(price.high), true)
+        .field("Instant Sell", optional_gp(price.low), true)
+        .field("Drop Value", format_gp(value), true)
+        .field("24 Hours", trend(price_manager, item_name, value, Duration::days(1)).await?, true)
+        .field("7 Days", trend(price_manager, item_name, value, Duration::days(7)).await?, true)
+        .field("30 Days", trend(price_manager, item_name, value, Duration::days(30)).await?, true);
+
+    if let Some(icon) = &mapping.icon {
+        embed = embed.thumbnail(format!("{}{}", WIKI_IMAGE_URL, icon.replace(' ', "_")));
+    }
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+        ))
+        .await?;
+
+    Ok(())
+}
+
+/// The drop value `ago` in the past and how much it has moved since
+async fn trend(price_manager: &PriceManager, item_name: &str, current: i64, ago: Duration) -> Result<String> {
+    let at = Utc::now().naive_utc() - ago;
+    let Some(snapshot) = price_manager.get_snapshot_before(item_name, at).await? else {
+        return Ok("No history yet".to_string());
+    };
+
+    if snapshot.value == 0 {
+        return Ok(format_gp(snapshot.value));
+    }
+
+    let change = (current - snapshot.value) as f64 / snapshot.value as f64 * 100.0;
+    Ok(format!("{} ({:+.1}%)", format_gp(snapshot.value), change))
+}
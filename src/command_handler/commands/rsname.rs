@@ -1,11 +1,25 @@
 use anyhow::Result;
 use serenity::all::{
+    ButtonStyle,
     CommandInteraction,
+    ComponentInteractionCollector,
+    CreateActionRow,
+    CreateButton,
     CreateInteractionResponse,
     CreateInteractionResponseMessage,
+    EditInteractionResponse,
 };
+use serenity::futures::StreamExt;
 use sqlx::SqlitePool;
 use crate::logger;
+use crate::unlinked::{self, PendingEvent};
+
+const CLAIM_BUTTON: &str = "rsname_claim";
+const SKIP_BUTTON: &str = "rsname_skip";
+/// How long the member has to claim the notifications seen before they linked the name
+const CLAIM_TIMEOUT_SECS: u64 = 300;
+/// Most pending notifications listed in the reply, the rest are summed up
+const CLAIM_LIST_LIMIT: usize = 10;
 
 pub async fn handle_rsname(
     command: &CommandInteraction,
@@ -51,7 +65,7 @@ pub async fn handle_rsname(
     .fetch_all(db)
     .await?
     .into_iter()
-    .filter_map(|record| Some(record.runescape_name))
+    .map(|record| record.runescape_name)
     .collect::<Vec<String>>();
     
     // Log the action
@@ -59,7 +73,7 @@ pub async fn handle_rsname(
         ctx,
         &discord_id,
         "LINKED RSNAME",
-        &format!("{}", rs_name)
+        rs_name
     ).await?;
     
     // Create response message
@@ -71,11 +85,122 @@ pub async fn handle_rsname(
                 existing.join(", "))
     };
     
+    // Notifications that came in before anyone had linked the name
+    let waiting = if result.rows_affected() > 0 {
+        unlinked::pending(db, Some(rs_name)).await?
+    } else {
+        Vec::new()
+    };
+    if waiting.is_empty() {
+        command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(message)
+            ))
+            .await?;
+        return Ok(());
+    }
+
     command
         .create_response(&ctx.http, CreateInteractionResponse::Message(
-            CreateInteractionResponseMessage::new().content(message)
+            CreateInteractionResponseMessage::new()
+                .content(format!("{}\n\n{}", message, claim_prompt(rs_name, &waiting)))
+                .components(claim_buttons())
         ))
         .await?;
-    
+
+    let response = command.get_response(&ctx.http).await?;
+    let interaction = ComponentInteractionCollector::new(ctx)
+        .message_id(response.id)
+        .author_id(command.user.id)
+        .timeout(std::time::Duration::from_secs(CLAIM_TIMEOUT_SECS))
+        .stream()
+        .next()
+        .await;
+
+    let Some(interaction) = interaction else {
+        command
+            .edit_response(&ctx.http, EditInteractionResponse::new()
+                .content(format!("{}\n\nThe earlier notifications for '{}' were left for a mod to check.", message, rs_name))
+                .components(Vec::new()))
+            .await?;
+        return Ok(());
+    };
+
+    if interaction.data.custom_id != CLAIM_BUTTON {
+        interaction
+            .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("{}\n\nThe earlier notifications for '{}' were left for a mod to check.", message, rs_name))
+                    .components(Vec::new())
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    interaction
+        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(format!("{}\n\nCrediting the earlier notifications...", message))
+                .components(Vec::new())
+        ))
+        .await?;
+
+    let user_name = command.member.as_ref()
+        .map(|m| m.display_name())
+        .unwrap_or(&command.user.name);
+    let credited = unlinked::credit(ctx, db, &discord_id, user_name, rs_name).await?;
+
+    logger::log_action(
+        ctx,
+        &discord_id,
+        "CLAIMED UNLINKED",
+        &format!("{} notifications for {}", credited.len(), rs_name)
+    ).await?;
+
+    command
+        .edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(truncate_lines(format!("{}\n\nCredited from before '{}' was linked:\n{}", message, rs_name, credited.join("\n")))))
+        .await?;
+
     Ok(())
+}
+
+fn claim_buttons() -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(CLAIM_BUTTON)
+            .label("They're mine")
+            .style(ButtonStyle::Success),
+        CreateButton::new(SKIP_BUTTON)
+            .label("Not mine")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+/// Lists what's waiting for the name and asks the member to confirm it's theirs
+fn claim_prompt(rs_name: &str, waiting: &[PendingEvent]) -> String {
+    let mut prompt = format!(
+        "The bot saw {} RuneLite notifications for '{}' before it was linked:\n",
+        waiting.len(),
+        rs_name
+    );
+    for event in waiting.iter().take(CLAIM_LIST_LIMIT) {
+        prompt += format!("{}\n", event.line()).as_str();
+    }
+    if waiting.len() > CLAIM_LIST_LIMIT {
+        prompt += format!("...and {} more\n", waiting.len() - CLAIM_LIST_LIMIT).as_str();
+    }
+    prompt += format!("Are they yours? Confirm within {} minutes to get their points.", CLAIM_TIMEOUT_SECS / 60).as_str();
+    prompt
+}
+
+/// Discord messages can't be longer than 2000 characters, so drop whole lines off the end until it fits
+fn truncate_lines(text: String) -> String {
+    if text.chars().count() <= 2000 {
+        return text;
+    }
+    let mut lines: Vec<&str> = text.lines().collect();
+    while lines.join("\n").chars().count() > 1980 {
+        lines.pop();
+    }
+    format!("{}\n...", lines.join("\n"))
 } 
\ No newline at end of file
@@ -40,7 +40,7 @@ pub async fn handle_rsname_remove(
     .fetch_all(db)
     .await?
     .into_iter()
-    .filter_map(|record| Some(record.runescape_name))
+    .map(|record| record.runescape_name)
     .collect::<Vec<String>>();
     
     // Log the action if we actually removed something
@@ -49,7 +49,7 @@ pub async fn handle_rsname_remove(
             ctx,
             &discord_id,
             "UNLINKED RSNAME",
-            &format!("{}", rs_name)
+            rs_name
         ).await?;
     }
     
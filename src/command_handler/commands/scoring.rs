@@ -0,0 +1,186 @@
+use anyhow::Result;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    CreateEmbed,
+};
+use sqlx::SqlitePool;
+use crate::command_handler::format_number;
+use crate::logger;
+use crate::scoring::{OverrideKind, ScoringRules, RULES};
+
+async fn respond(command: &CommandInteraction, ctx: &serenity::prelude::Context, content: String) -> Result<()> {
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+        ))
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_scoring(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let rules = ScoringRules::load(db).await?;
+
+    let rules_text = RULES
+        .iter()
+        .map(|(name, description)| {
+            format!("`{}` = **{}** - {}", name, rules.get(name).unwrap_or_default(), description)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut overrides: Vec<_> = rules.overrides.iter().collect();
+    overrides.sort_by(|a, b| (a.0.0.as_str(), &a.0.1).cmp(&(b.0.0.as_str(), &b.0.1)));
+    let overrides_text = if overrides.is_empty() {
+        "No item overrides".to_string()
+    } else {
+        overrides
+            .iter()
+            .map(|((kind, item_name), points)| {
+                format!("• {} {}: {} points", kind.as_str(), item_name, format_number(**points))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::new()
+        .title("Scoring Rules")
+        .color(0x00ff00)
+        .description(rules_text)
+        .field("Item Overrides", overrides_text, false);
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .embed(embed)
+        ))
+        .await?;
+
+    Ok(())
+}
+
+pub async fn handle_scoring_set(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let options = &command.data.options;
+    let rule = options
+        .iter()
+        .find(|opt| opt.name == "rule")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Rule not provided"))?;
+    let value = options
+        .iter()
+        .find(|opt| opt.name == "value")
+        .and_then(|opt| opt.value.as_f64())
+        .ok_or_else(|| anyhow::anyhow!("Value not provided"))?;
+
+    let mut rules = ScoringRules::load(db).await?;
+    let Some(old_value) = rules.get(rule) else {
+        return respond(command, ctx, format!("There's no scoring rule called `{}`.", rule)).await;
+    };
+
+    rules.set(rule, value);
+    if !value.is_finite() || value < 0.0 {
+        return respond(command, ctx, "Scoring rules can't be negative.".to_string()).await;
+    }
+    if rules.gp_per_point < 1.0 {
+        return respond(command, ctx, "`gp_per_point` must be at least 1.".to_string()).await;
+    }
+    if rules.rare_max_rate <= 0.0 || rules.rare_max_rate >= rules.uncommon_max_rate {
+        return respond(command, ctx, "`rare_max_rate` must be above 0 and below `uncommon_max_rate`.".to_string()).await;
+    }
+
+    sqlx::query!(
+        "INSERT INTO scoring_rules (name, value) VALUES (?, ?)
+         ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+        rule,
+        value
+    )
+    .execute(db)
+    .await?;
+
+    respond(command, ctx, format!(
+        "`{}` changed from {} to {}. New drops and collection log entries will use it; existing ones keep their points.",
+        rule, old_value, value
+    )).await?;
+
+    let commanding_officer_id = command.user.id.to_string();
+    logger::log_action(ctx, &commanding_officer_id, "SCORING RULE", &format!("{}: {} -> {}", rule, old_value, value)).await?;
+    Ok(())
+}
+
+pub async fn handle_scoring_override(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let options = &command.data.options;
+    let kind = options
+        .iter()
+        .find(|opt| opt.name == "type")
+        .and_then(|opt| opt.value.as_str())
+        .and_then(OverrideKind::parse)
+        .ok_or_else(|| anyhow::anyhow!("Override type not provided"))?;
+    let item_name = options
+        .iter()
+        .find(|opt| opt.name == "item")
+        .and_then(|opt| opt.value.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Item name not provided"))?;
+    let points = options
+        .iter()
+        .find(|opt| opt.name == "points")
+        .and_then(|opt| opt.value.as_i64());
+
+    let kind_name = kind.as_str();
+    let (response, details) = match points {
+        Some(points) => {
+            sqlx::query!(
+                "INSERT INTO scoring_overrides (kind, item_name, points) VALUES (?, ?, ?)
+                 ON CONFLICT(kind, item_name) DO UPDATE SET points = excluded.points",
+                kind_name,
+                item_name,
+                points
+            )
+            .execute(db)
+            .await?;
+
+            let per = if kind == OverrideKind::Drop { " each" } else { "" };
+            (
+                format!("{} {} now gives {} points{}.", item_name, kind_name, format_number(points), per),
+                format!("{} {} = {} points", kind_name, item_name, points),
+            )
+        }
+        None => {
+            let removed = sqlx::query!(
+                "DELETE FROM scoring_overrides WHERE kind = ? AND item_name = ?",
+                kind_name,
+                item_name
+            )
+            .execute(db)
+            .await?
+            .rows_affected();
+
+            if removed == 0 {
+                return respond(command, ctx, format!("{} has no {} override.", item_name, kind_name)).await;
+            }
+            (
+                format!("{} {} is back to the normal scoring rules.", item_name, kind_name),
+                format!("{} {} override removed", kind_name, item_name),
+            )
+        }
+    };
+
+    respond(command, ctx, response).await?;
+
+    let commanding_officer_id = command.user.id.to_string();
+    logger::log_action(ctx, &commanding_officer_id, "SCORING OVERRIDE", &details).await?;
+    Ok(())
+}
@@ -7,6 +7,10 @@ use serenity::all::{
 };
 use sqlx::SqlitePool;
 use crate::command_handler::{format_points, format_number, format_gp};
+use crate::screenshots;
+
+/// Embed fields can't be longer than this
+const FIELD_LIMIT: usize = 1024;
 
 pub async fn handle_stats(
     command: &CommandInteraction,
@@ -76,7 +80,7 @@ pub async fn handle_stats(
 
             // Get 5 most recent drops
             let recent_drops = sqlx::query!(
-                "SELECT item_name, quantity, value, timestamp 
+                "SELECT item_name, quantity, value, timestamp, screenshot_url 
                  FROM drops 
                  WHERE discord_id = ? 
                  ORDER BY timestamp DESC 
@@ -88,7 +92,7 @@ pub async fn handle_stats(
 
             // Get 5 most recent collection log entries
             let recent_clogs = sqlx::query!(
-                "SELECT item_name, points, timestamp 
+                "SELECT item_name, points, timestamp, screenshot_url 
                  FROM collection_log_entries 
                  WHERE discord_id = ? 
                  ORDER BY timestamp DESC 
@@ -98,6 +102,29 @@ pub async fn handle_stats(
             .fetch_all(db)
             .await?;
 
+            // Get the latest admin adjustments
+            let recent_adjustments = sqlx::query!(
+                "SELECT delta, reason 
+                 FROM point_adjustments 
+                 WHERE discord_id = ? 
+                 ORDER BY id DESC 
+                 LIMIT 5",
+                discord_id
+            )
+            .fetch_all(db)
+            .await?;
+
+            // Get pets, oldest first
+            let pets = sqlx::query!(
+                r#"SELECT pet_name, duplicate AS "duplicate!: bool" 
+                 FROM pets 
+                 WHERE discord_id = ? 
+                 ORDER BY timestamp"#,
+                discord_id
+            )
+            .fetch_all(db)
+            .await?;
+
             // Get most valuable drop
             let most_valuable_drop = sqlx::query!(
                 "SELECT item_name, quantity, value 
@@ -110,6 +137,19 @@ pub async fn handle_stats(
             .fetch_optional(db)
             .await?;
 
+            // Get the sources that have paid out the most
+            let top_sources = sqlx::query!(
+                r#"SELECT source AS "source!: String", COUNT(*) AS "drops!: i64", SUM(value) AS "value!: i64"
+                 FROM drops 
+                 WHERE discord_id = ? AND source IS NOT NULL 
+                 GROUP BY source 
+                 ORDER BY SUM(value) DESC 
+                 LIMIT 5"#,
+                discord_id
+            )
+            .fetch_all(db)
+            .await?;
+
             // Get rarest collection log entry
             let rarest_clog = sqlx::query!(
                 "SELECT item_name, points 
@@ -158,31 +198,77 @@ pub async fn handle_stats(
             let recent_drops_text = if recent_drops.is_empty() {
                 "No drops recorded yet".to_string()
             } else {
-                recent_drops
-                    .iter()
-                    .map(|drop| {
+                with_screenshots(recent_drops.iter().map(|drop| {
+                    (
                         format!(
                             "• {}x {} ({})",
                             format_number(drop.quantity),
                             drop.item_name,
                             format_gp(drop.value)
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n")
+                        ),
+                        drop.screenshot_url.as_deref(),
+                    )
+                }))
             };
 
             // Format recent collection log entries
             let recent_clogs_text = if recent_clogs.is_empty() {
                 "No collection log entries yet".to_string()
             } else {
-                recent_clogs
-                    .iter()
-                    .map(|clog| {
+                with_screenshots(recent_clogs.iter().map(|clog| {
+                    (
                         format!(
                             "• {} (+{} pts)",
                             clog.item_name,
                             format_number(clog.points)
+                        ),
+                        clog.screenshot_url.as_deref(),
+                    )
+                }))
+            };
+
+            // Format adjustments
+            let recent_adjustments_text = recent_adjustments
+                .iter()
+                .map(|adjustment| {
+                    format!(
+                        "• {:+} pts: {}",
+                        adjustment.delta,
+                        adjustment.reason
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            // Format pets, the plain text notifications don't say which pet it was
+            let pet_count = pets.iter().filter(|pet| !pet.duplicate).count();
+            let duplicate_count = pets.len() - pet_count;
+            let pet_names: Vec<&str> = pets
+                .iter()
+                .filter(|pet| !pet.duplicate)
+                .map(|pet| pet.pet_name.as_deref().unwrap_or("Unknown pet"))
+                .collect();
+            let mut pets_text = format!("{} pets", format_number(pet_count as i64));
+            if duplicate_count > 0 {
+                pets_text += format!(" ({} duplicates)", format_number(duplicate_count as i64)).as_str();
+            }
+            let pets_with_names = format!("{}\n{}", pets_text, pet_names.join(", "));
+            if pets_with_names.chars().count() <= FIELD_LIMIT {
+                pets_text = pets_with_names;
+            }
+
+            // Format top sources
+            let top_sources_text = if top_sources.is_empty() {
+                "No drop sources recorded yet".to_string()
+            } else {
+                top_sources
+                    .iter()
+                    .map(|row| {
+                        format!(
+                            "• {}: {} drops ({})",
+                            row.source,
+                            format_number(row.drops),
+                            format_gp(row.value)
                         )
                     })
                     .collect::<Vec<_>>()
@@ -212,9 +298,9 @@ pub async fn handle_stats(
                 })
                 .unwrap_or_else(|| "No collection log entries yet".to_string());
 
-            let embed = CreateEmbed::new()
+            let mut embed = CreateEmbed::new()
                 .title(format!("{}'s Profile", command.member.as_ref()
-                    .and_then(|m| Some(m.display_name()))
+                    .map(|m| m.display_name())
                     .unwrap_or(&command.user.name)))
                 .color(0x00ff00)
                 .thumbnail(command.user.face())
@@ -227,8 +313,15 @@ pub async fn handle_stats(
                 .field(format!("Progress to {}", next_rank_name), progress, false)
                 .field("Recent Drops", recent_drops_text, false)
                 .field("Recent Collection Log", recent_clogs_text, false)
+                .field("Top Sources", top_sources_text, false)
                 .field("Most Valuable Drop", most_valuable_text, true)
                 .field("Rarest Collection Log Entry", rarest_clog_text, true);
+            if !pets.is_empty() {
+                embed = embed.field("Pets", pets_text, false);
+            }
+            if !recent_adjustments.is_empty() {
+                embed = embed.field("Point Adjustments", recent_adjustments_text, false);
+            }
 
             command
                 .create_response(&ctx.http, CreateInteractionResponse::Message(
@@ -240,7 +333,7 @@ pub async fn handle_stats(
         None => {
             let embed = CreateEmbed::new()
                 .title(format!("{}'s Profile", command.member.as_ref()
-                    .and_then(|m| Some(m.display_name()))
+                    .map(|m| m.display_name())
                     .unwrap_or(&command.user.name)))
                 .color(0xff0000)
                 .thumbnail(command.user.face())
@@ -256,4 +349,20 @@ pub async fn handle_stats(
     }
 
     Ok(())
-} 
\ No newline at end of file
+} 
+
+/// Joins the lines with their screenshot links, leaving the links out if they'd make the field too long
+fn with_screenshots<'a>(lines: impl Iterator<Item = (String, Option<&'a str>)>) -> String {
+    let lines: Vec<_> = lines.collect();
+    let linked = lines
+        .iter()
+        .map(|(line, url)| format!("{}{}", line, screenshots::describe(*url, None)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if linked.chars().count() <= FIELD_LIMIT {
+        linked
+    } else {
+        lines.into_iter().map(|(line, _)| line).collect::<Vec<_>>().join("\n")
+    }
+}
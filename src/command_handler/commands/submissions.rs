@@ -0,0 +1,403 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serenity::all::{
+    Attachment,
+    ButtonStyle,
+    ChannelId,
+    CommandInteraction,
+    ComponentInteraction,
+    CreateActionRow,
+    CreateButton,
+    CreateEmbed,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    CreateMessage,
+    EditInteractionResponse,
+};
+use sqlx::SqlitePool;
+use crate::command_handler::{format_gp, format_number};
+use crate::command_handler::commands::clog::{self, NewClog};
+use crate::command_handler::commands::drop::{self, NewDrop};
+use crate::command_handler::commands::pet::{self, NewPet};
+use crate::config::ConfigKey;
+use crate::logger;
+use crate::screenshots::Screenshot;
+use crate::splits::{self, Share};
+
+/// Button IDs are these prefixes followed by the submission ID, so they keep working after a restart
+const APPROVE_PREFIX: &str = "submission_approve:";
+const DENY_PREFIX: &str = "submission_deny:";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SubmissionKind {
+    Drop,
+    Clog,
+    Pet,
+}
+
+impl SubmissionKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionKind::Drop => "drop",
+            SubmissionKind::Clog => "clog",
+            SubmissionKind::Pet => "pet",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "drop" => SubmissionKind::Drop,
+            "pet" => SubmissionKind::Pet,
+            _ => SubmissionKind::Clog,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            SubmissionKind::Drop => "drop",
+            SubmissionKind::Clog => "collection log entry",
+            SubmissionKind::Pet => "pet",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            SubmissionKind::Drop => "Drop",
+            SubmissionKind::Clog => "Collection log",
+            SubmissionKind::Pet => "Pet",
+        }
+    }
+}
+
+/// A manual /drop, /clog or /pet waiting for a mod
+pub struct Submission<'a> {
+    pub kind: SubmissionKind,
+    pub item_name: &'a str,
+    pub quantity: i64,
+    /// Total value, drops only
+    pub value: Option<i64>,
+    pub source: Option<&'a str>,
+    pub dropped_at: Option<NaiveDateTime>,
+    pub screenshot: &'a Screenshot,
+    /// Teammates a drop is split with, submitter first. Empty for a solo drop
+    pub shares: &'a [Share],
+}
+
+/// Whether manual submissions have to be approved by a mod (`REQUIRE_APPROVAL`)
+pub async fn approval_required(ctx: &serenity::prelude::Context) -> bool {
+    let data = ctx.data.read().await;
+    data.get::<ConfigKey>().is_some_and(|config| config.require_approval)
+}
+
+/// The command's `screenshot` attachment, if one was given
+pub fn screenshot_attachment(command: &CommandInteraction) -> Option<&Attachment> {
+    let attachment_id = command.data.options
+        .iter()
+        .find(|opt| opt.name == "screenshot")
+        .and_then(|opt| opt.value.as_attachment_id())?;
+    command.data.resolved.attachments.get(&attachment_id)
+}
+
+fn review_buttons(submission_id: i64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}{}", APPROVE_PREFIX, submission_id))
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}{}", DENY_PREFIX, submission_id))
+            .label("Deny")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Short description of what was submitted, e.g. "2x Dragon bones (5.2K gp) from Vorkath"
+fn describe_item(item_name: &str, quantity: i64, value: Option<i64>, source: Option<&str>) -> String {
+    let mut text = match value {
+        Some(value) => format!("{}x {} ({})", format_number(quantity), item_name, format_gp(value)),
+        None => item_name.to_string(),
+    };
+    if let Some(source) = source {
+        text += format!(" from {}", source).as_str();
+    }
+    text
+}
+
+/// "<@1> ×2, <@2> ×1" for the mod channel
+fn describe_shares(shares: &[Share]) -> String {
+    shares
+        .iter()
+        .map(|share| format!("<@{}> ×{}", share.discord_id, share.weight))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Stores the submission, posts it to the mod channel with Approve/Deny buttons and lets the member know it's waiting
+pub async fn submit(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+    submission: &Submission<'_>,
+) -> Result<()> {
+    let discord_id = command.user.id.to_string();
+    let user_name = command.member.as_ref()
+        .map(|m| m.display_name())
+        .unwrap_or(&command.user.name);
+
+    let kind = submission.kind.as_str();
+
+    // Drops can repeat, but a clog item or pet can only be waiting once
+    if submission.kind != SubmissionKind::Drop {
+        let pending = sqlx::query!(
+            "SELECT id FROM pending_submissions WHERE status = 'pending' AND kind = ? AND discord_id = ? AND item_name = ?",
+            kind,
+            discord_id,
+            submission.item_name
+        )
+        .fetch_optional(db)
+        .await?;
+        if pending.is_some() {
+            command
+                .create_response(&ctx.http, CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(format!("Your {} submission is already waiting for a mod.", submission.item_name))
+                        .ephemeral(true)
+                ))
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let channel_id = command.channel_id.to_string();
+    let screenshot_url = submission.screenshot.url.as_deref().unwrap_or_default();
+    let shares = splits::encode(submission.shares);
+    let submission_id = sqlx::query!(
+        "INSERT INTO pending_submissions (kind, discord_id, user_name, item_name, quantity, value, source, dropped_at, screenshot_url, screenshot_path, shares, channel_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        kind,
+        discord_id,
+        user_name,
+        submission.item_name,
+        submission.quantity,
+        submission.value,
+        submission.source,
+        submission.dropped_at,
+        screenshot_url,
+        submission.screenshot.path,
+        shares,
+        channel_id
+    )
+    .execute(db)
+    .await?
+    .last_insert_rowid();
+
+    let item_text = describe_item(submission.item_name, submission.quantity, submission.value, submission.source);
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} submission #{}", submission.kind.title(), submission_id))
+        .description(format!("<@{}> submitted {}\n[Screenshot]({})", discord_id, item_text, screenshot_url))
+        .image(screenshot_url);
+    if let Some(dropped_at) = submission.dropped_at {
+        embed = embed.field("When", dropped_at.format("%Y-%m-%d %H:%M UTC").to_string(), true);
+    }
+    if !submission.shares.is_empty() {
+        embed = embed.field("Split", describe_shares(submission.shares), true);
+    }
+
+    let mod_channel_id = {
+        let data = ctx.data.read().await;
+        data.get::<ConfigKey>().map(|config| config.mod_channel_id)
+    };
+    if let Some(mod_channel_id) = mod_channel_id {
+        mod_channel_id
+            .send_message(&ctx.http, CreateMessage::new()
+                .embed(embed)
+                .components(review_buttons(submission_id)))
+            .await?;
+    }
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(format!("Submitted {} for approval, you'll get your points once a mod has checked it!", item_text))
+        ))
+        .await?;
+
+    logger::log_action(ctx, &discord_id, &format!("SUBMITTED {}", kind.to_uppercase()), &format!("{} (#{})", item_text, submission_id)).await?;
+    Ok(())
+}
+
+/// Approve/Deny button on a submission in the mod channel
+pub async fn handle_submission_review(
+    component: &ComponentInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let custom_id = component.data.custom_id.as_str();
+    let (approve, submission_id) = match (custom_id.strip_prefix(APPROVE_PREFIX), custom_id.strip_prefix(DENY_PREFIX)) {
+        (Some(id), _) => (true, id),
+        (_, Some(id)) => (false, id),
+        _ => return Ok(()),
+    };
+    let submission_id: i64 = submission_id.parse()?;
+    let reviewer_id = component.user.id.to_string();
+
+    let Some(submission) = sqlx::query!(
+        "SELECT kind, discord_id, user_name, item_name, quantity, value, source, dropped_at, screenshot_url, screenshot_path, shares, channel_id, status
+         FROM pending_submissions WHERE id = ?",
+        submission_id
+    )
+    .fetch_optional(db)
+    .await? else {
+        return Ok(());
+    };
+
+    if submission.discord_id == reviewer_id {
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("You can't review your own submission.")
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    let kind = SubmissionKind::parse(&submission.kind);
+    let item_text = describe_item(&submission.item_name, submission.quantity, submission.value, submission.source.as_deref());
+
+    // A clog or pet the member has logged some other way since can't be approved again
+    let already_logged = match kind {
+        SubmissionKind::Drop => false,
+        SubmissionKind::Clog => clog::logged_at(db, &submission.discord_id, &submission.item_name).await.is_some(),
+        SubmissionKind::Pet => pet::pet_logged_at(db, &submission.discord_id, &submission.item_name).await.is_some(),
+    };
+    let status = if approve && !already_logged {"approved"} else {"denied"};
+
+    // Claiming it with the status check means two mods clicking at once can't both award the points
+    let claimed = sqlx::query!(
+        "UPDATE pending_submissions SET status = ?, reviewer_id = ?, reviewed_at = CURRENT_TIMESTAMP
+         WHERE id = ? AND status = 'pending'",
+        status,
+        reviewer_id,
+        submission_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+    if claimed == 0 {
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("This submission was already {}.", submission.status))
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let outcome = if status == "approved" {
+        let screenshot = Screenshot::new(Some(submission.screenshot_url.clone()), submission.screenshot_path.clone());
+        let recorded = match kind {
+            SubmissionKind::Drop => {
+                let value = submission.value.unwrap_or(0);
+                let new_drop = NewDrop {
+                    discord_id: &submission.discord_id,
+                    user_name: &submission.user_name,
+                    item_name: &submission.item_name,
+                    quantity: submission.quantity,
+                    total_value: value,
+                    source: submission.source.as_deref(),
+                    when: submission.dropped_at,
+                    screenshot: &screenshot,
+                    actor_id: &reviewer_id,
+                };
+                match &submission.shares {
+                    Some(shares) => {
+                        let shares = splits::decode(ctx, shares).await;
+                        drop::record_split_drop(ctx, db, &new_drop, &shares)
+                            .await
+                            .map(|recorded| Some(drop::split_recorded_message(submission.quantity, &submission.item_name, &format_gp(value), &shares, &recorded)))
+                    }
+                    None => drop::record_drop(ctx, db, &new_drop)
+                        .await
+                        .map(|(points, points_update)| Some(drop::drop_recorded_message(submission.quantity, &submission.item_name, &format_gp(value), points, &points_update))),
+                }
+            }
+            SubmissionKind::Clog => {
+                clog::record_clog(ctx, db, &NewClog {
+                    discord_id: &submission.discord_id,
+                    user_name: &submission.user_name,
+                    item_name: &submission.item_name,
+                    screenshot: &screenshot,
+                    actor_id: &reviewer_id,
+                    when: submission.dropped_at,
+                })
+                .await
+                .map(|recorded| recorded.map(|(points, points_update)| clog::clog_recorded_message(&submission.item_name, points, &points_update)))
+            }
+            SubmissionKind::Pet => {
+                pet::record_pet(ctx, db, &NewPet {
+                    discord_id: &submission.discord_id,
+                    user_name: &submission.user_name,
+                    pet_name: Some(&submission.item_name),
+                    duplicate: false,
+                    screenshot: &screenshot,
+                    actor_id: Some(&reviewer_id),
+                    message_id: None,
+                    when: submission.dropped_at,
+                })
+                .await
+                .map(|(points, points_update)| Some(pet::pet_recorded_message(Some(&submission.item_name), false, points, &points_update)))
+            }
+        };
+
+        match recorded {
+            Ok(Some(message)) => message,
+            Ok(None) => format!("Your {} submission was approved, but the item is no longer in the collection log so no points were awarded.", submission.item_name),
+            Err(why) => {
+                // Put it back in the queue so it can be approved again once whatever failed is fixed
+                sqlx::query!(
+                    "UPDATE pending_submissions SET status = 'pending', reviewer_id = NULL, reviewed_at = NULL WHERE id = ?",
+                    submission_id
+                )
+                .execute(db)
+                .await?;
+                return Err(why);
+            }
+        }
+    } else if already_logged {
+        format!(
+            "Your {} submission was closed, it's already in your {}.",
+            submission.item_name,
+            if kind == SubmissionKind::Pet {"pets"} else {"collection log"}
+        )
+    } else {
+        format!("Your {} submission ({}) was denied by a mod.", kind.describe(), item_text)
+    };
+
+    component
+        .edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(format!("{} by <@{}>", if status == "approved" {"✅ Approved"} else {"❌ Denied"}, reviewer_id))
+            .components(Vec::new()))
+        .await?;
+
+    if let Ok(channel_id) = submission.channel_id.parse::<u64>() {
+        if let Err(why) = ChannelId::new(channel_id)
+            .say(&ctx.http, format!("<@{}> {}", submission.discord_id, outcome))
+            .await
+        {
+            tracing::error!("Failed to notify {} about submission #{}: {:?}", submission.discord_id, submission_id, why);
+        }
+    }
+
+    logger::log_action(
+        ctx,
+        &reviewer_id,
+        &format!("{} {}", status.to_uppercase(), kind.as_str().to_uppercase()),
+        &format!("#{} from <@{}>: {}", submission_id, submission.discord_id, item_text),
+    ).await?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use serenity::all::{CommandInteraction, UserId};
+
+/// The member a command acts on. Admin commands like the `admin_` variants and /points_adjust take a
+/// `user` option so mods can act for someone else, everything else acts on whoever ran the command
+pub struct Target {
+    pub discord_id: String,
+    pub user_name: String,
+    /// Discord ID of whoever ran the command
+    pub actor_id: String,
+}
+
+impl Target {
+    pub fn from_command(command: &CommandInteraction) -> Self {
+        let actor_id = command.user.id.to_string();
+        let own = || Self {
+            discord_id: actor_id.clone(),
+            user_name: command.member.as_ref()
+                .map(|m| m.display_name())
+                .unwrap_or(&command.user.name)
+                .to_string(),
+            actor_id: actor_id.clone(),
+        };
+
+        let Some(user_id) = user_option(command) else {
+            return own();
+        };
+        if user_id == command.user.id {
+            return own();
+        }
+
+        let resolved = &command.data.resolved;
+        let user_name = resolved.members.get(&user_id)
+            .and_then(|member| member.nick.clone())
+            .or_else(|| resolved.users.get(&user_id).map(|user| user.display_name().to_string()))
+            .unwrap_or_else(|| format!("Unknown ({})", user_id));
+
+        Self {
+            discord_id: user_id.to_string(),
+            user_name,
+            actor_id,
+        }
+    }
+
+    /// Whether a mod is acting for another member
+    pub fn on_behalf(&self) -> bool {
+        self.discord_id != self.actor_id
+    }
+
+    /// Reply text, addressed to the member when a mod recorded it for them
+    pub fn reply(&self, message: String) -> String {
+        if self.on_behalf() {
+            format!("<@{}> {}", self.discord_id, message)
+        } else {
+            message
+        }
+    }
+
+    /// Suffix for a removal log line naming the mod, empty when members remove their own entries
+    pub fn removed_by(&self) -> String {
+        if self.on_behalf() {
+            format!(", removed by <@{}>", self.actor_id)
+        } else {
+            String::new()
+        }
+    }
+}
+
+fn user_option(command: &CommandInteraction) -> Option<UserId> {
+    command.data.options
+        .iter()
+        .find(|opt| opt.name == "user")
+        .and_then(|opt| opt.value.as_user_id())
+}
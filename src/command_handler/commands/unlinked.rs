@@ -0,0 +1,96 @@
+use anyhow::Result;
+use serenity::all::{
+    CommandInteraction,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use crate::logger;
+use crate::unlinked;
+
+/// Most entries listed for one RS name, Discord messages are capped at 2000 characters
+const LIST_LIMIT: usize = 15;
+
+/// ADMIN: RuneLite notifications for RS names nobody has linked, by name or one name in full
+pub async fn handle_unlinked(
+    command: &CommandInteraction,
+    ctx: &serenity::prelude::Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let options = &command.data.options;
+    let rs_name = options
+        .iter()
+        .find(|opt| opt.name == "rsname")
+        .and_then(|opt| opt.value.as_str())
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let dismiss = options
+        .iter()
+        .find(|opt| opt.name == "dismiss")
+        .and_then(|opt| opt.value.as_bool())
+        .unwrap_or(false);
+
+    let message = match (rs_name, dismiss) {
+        (None, true) => "Give the `rsname` whose notifications should be dismissed.".to_string(),
+        (Some(rs_name), true) => {
+            let actor_id = command.user.id.to_string();
+            let dismissed = unlinked::dismiss(db, rs_name, &actor_id).await?;
+            if dismissed > 0 {
+                logger::log_action(ctx, &actor_id, "DISMISSED UNLINKED", &format!("{} notifications for {}", dismissed, rs_name)).await?;
+            }
+            format!("Dismissed {} notifications for '{}'.", dismissed, rs_name)
+        }
+        (Some(rs_name), false) => {
+            let waiting = unlinked::pending(db, Some(rs_name)).await?;
+            if waiting.is_empty() {
+                format!("Nothing is waiting for '{}'.", rs_name)
+            } else {
+                let mut text = format!("{} notifications waiting for '{}':\n", waiting.len(), rs_name);
+                for event in waiting.iter().take(LIST_LIMIT) {
+                    text += format!("{}\n", event.line()).as_str();
+                }
+                if waiting.len() > LIST_LIMIT {
+                    text += format!("...and {} more\n", waiting.len() - LIST_LIMIT).as_str();
+                }
+                text += "They're offered to whoever links the name with `/rsname`, or use `dismiss` to drop them.";
+                text
+            }
+        }
+        (None, false) => {
+            let waiting = unlinked::pending(db, None).await?;
+            // Grouped case-insensitively, the way RS names are matched
+            let mut by_name: BTreeMap<String, (String, usize)> = BTreeMap::new();
+            for event in &waiting {
+                by_name
+                    .entry(event.rs_name.to_lowercase())
+                    .or_insert_with(|| (event.rs_name.clone(), 0))
+                    .1 += 1;
+            }
+
+            if by_name.is_empty() {
+                "No notifications are waiting for an RS name to be linked.".to_string()
+            } else {
+                let mut text = String::from("RS names with notifications waiting to be linked:\n");
+                for (name, count) in by_name.values().take(LIST_LIMIT * 2) {
+                    text += format!("• {}: {}\n", name, count).as_str();
+                }
+                if by_name.len() > LIST_LIMIT * 2 {
+                    text += format!("...and {} more names\n", by_name.len() - LIST_LIMIT * 2).as_str();
+                }
+                text += "Use `/unlinked rsname:<name>` to see one in full.";
+                text
+            }
+        }
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(message)
+                .ephemeral(true)
+        ))
+        .await?;
+
+    Ok(())
+}
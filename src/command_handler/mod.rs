@@ -30,7 +30,49 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
             "The quantity of items (default: 1)"
         )
         .required(false)
-        .min_int_value(1)))
+        .min_int_value(1))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "source",
+            "Where the drop came from (boss, raid, activity)"
+        )
+        .required(false)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "when",
+            "When you got it, UTC (YYYY-MM-DD or YYYY-MM-DD HH:MM), to use the prices from then"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "teammates",
+            "Teammates to split the drop with, as @mentions"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "shares",
+            "Custom split, the recipient's share first, e.g. `2 1 1` (default: even)"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Attachment,
+            "screenshot",
+            "Screenshot of the drop, needed when drops have to be approved by a mod"
+        )
+        .required(false)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("price")
+        .description("Look up an item's current price and recent trend")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "item",
+            "The name of the item"
+        )
+        .required(true)
+        .set_autocomplete(true)))
     .await?;
 
     Command::create_global_command(&ctx.http, CreateCommand::new("drop_remove")
@@ -53,7 +95,13 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
             "The name of the collection log item"
         )
         .required(true)
-        .set_autocomplete(true)))
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Attachment,
+            "screenshot",
+            "Screenshot of the item, needed when entries have to be approved by a mod"
+        )
+        .required(false)))
     .await?;
 
     Command::create_global_command(&ctx.http, CreateCommand::new("clog_remove")
@@ -68,12 +116,93 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
         .min_int_value(1)))
     .await?;
 
+    Command::create_global_command(&ctx.http, CreateCommand::new("pet")
+        .description("Record a pet")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "pet",
+            "The pet you got"
+        )
+        .required(true)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Attachment,
+            "screenshot",
+            "Screenshot of the pet, needed when pets have to be approved by a mod"
+        )
+        .required(false)))
+    .await?;
+
     Command::create_global_command(&ctx.http, CreateCommand::new("points")
         .description("Check your points total"))
     .await?;
 
+    Command::create_global_command(&ctx.http, CreateCommand::new("points_history")
+        .description("See where a member's points came from")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member to look up (default: you)"
+        )
+        .required(false)))
+    .await?;
+
     Command::create_global_command(&ctx.http, CreateCommand::new("leaderboard")
-        .description("View the points leaderboard"))
+        .description("View the points leaderboard")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "scope",
+            "Time range to rank (default: all time)"
+        )
+        .required(false)
+        .add_string_choice("All time", "all_time")
+        .add_string_choice("Past 7 days", "week")
+        .add_string_choice("Past 30 days", "month")
+        .add_string_choice("Custom range (use from/to)", "custom"))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "metric",
+            "What to rank by (default: points)"
+        )
+        .required(false)
+        .add_string_choice("Points", "points")
+        .add_string_choice("GP value", "gp")
+        .add_string_choice("Items received", "drops")
+        .add_string_choice("Collection log count", "clogs")
+        .add_string_choice("Pet count", "pets"))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "from",
+            "Start date for a custom range (YYYY-MM-DD)"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "to",
+            "End date for a custom range, inclusive (YYYY-MM-DD)"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "item",
+            "Only count this item"
+        )
+        .required(false)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "source",
+            "Only count drops from this boss or activity"
+        )
+        .required(false)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "category",
+            "Only count items in this collection log category"
+        )
+        .required(false)
+        .set_autocomplete(true)))
     .await?;
 
     Command::create_global_command(&ctx.http, CreateCommand::new("stats")
@@ -108,7 +237,25 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
 
     Command::create_global_command(&ctx.http, CreateCommand::new("recalculate")
         .description("ADMIN: Recalculate all points based on clamped categories.")
-        .default_member_permissions(admin_permission_set))
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "dry_run",
+            "Preview the changes and confirm them before anything is saved"
+        )
+        .required(false)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("recalculate_undo")
+        .description("ADMIN: Revert the point changes made by a recalculation.")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "batch",
+            "The recalculation to undo"
+        )
+        .required(true)
+        .set_autocomplete(true)))
     .await?;
 
     Command::create_global_command(&ctx.http, CreateCommand::new("clamp")
@@ -120,7 +267,14 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
             "The name of the category"
         )
         .required(true)
-        .set_autocomplete(true)))
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "max_points",
+            "Most points an item in this category can give (defaults to the clamp_cap rule)"
+        )
+        .required(false)
+        .min_int_value(1)))
     .await?;
 
     Command::create_global_command(&ctx.http, CreateCommand::new("unclamp")
@@ -159,6 +313,247 @@ pub async fn register_commands(ctx: &Context) -> Result<()> {
         .set_autocomplete(true)))
     .await?;
 
+    Command::create_global_command(&ctx.http, CreateCommand::new("scoring")
+        .description("ADMIN: Show the point rules and item overrides.")
+        .default_member_permissions(admin_permission_set))
+    .await?;
+
+    let mut rule_option = CreateCommandOption::new(
+        CommandOptionType::String,
+        "rule",
+        "The rule to change"
+    )
+    .required(true);
+    for (name, _) in crate::scoring::RULES {
+        rule_option = rule_option.add_string_choice(*name, *name);
+    }
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("scoring_set")
+        .description("ADMIN: Change a point rule. Only affects new drops and clogs.")
+        .default_member_permissions(admin_permission_set)
+        .add_option(rule_option)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Number,
+            "value",
+            "The new value"
+        )
+        .required(true)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("scoring_override")
+        .description("ADMIN: Give an item fixed points, or leave out points to remove the override.")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "type",
+            "Whether this is for drops or collection log entries"
+        )
+        .required(true)
+        .add_string_choice("Drop (points per item)", "drop")
+        .add_string_choice("Collection log", "clog"))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "item",
+            "The name of the item"
+        )
+        .required(true)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "points",
+            "Fixed points to award (leave empty to remove the override)"
+        )
+        .required(false)
+        .min_int_value(0)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("rank_sync")
+        .description("ADMIN: Sync every member's rank roles with their current points.")
+        .default_member_permissions(admin_permission_set))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("admin_drop")
+        .description("ADMIN: Record a drop for another member")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member who got the drop"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "item",
+            "The name of the item"
+        )
+        .required(true)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "quantity",
+            "The quantity of items (default: 1)"
+        )
+        .required(false)
+        .min_int_value(1))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "source",
+            "Where the drop came from (boss, raid, activity)"
+        )
+        .required(false)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "when",
+            "When they got it, UTC (YYYY-MM-DD or YYYY-MM-DD HH:MM), to use the prices from then"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "teammates",
+            "Teammates to split the drop with, as @mentions"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "shares",
+            "Custom split, the recipient's share first, e.g. `2 1 1` (default: even)"
+        )
+        .required(false))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Attachment,
+            "screenshot",
+            "Screenshot of the drop"
+        )
+        .required(false)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("admin_drop_remove")
+        .description("ADMIN: Remove a drop from another member")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member to remove the drop from"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "id",
+            "The ID of the drop to remove (leave empty to see their recent drops)"
+        )
+        .required(false)
+        .set_autocomplete(true)
+        .min_int_value(1)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("admin_clog")
+        .description("ADMIN: Record a collection log item for another member")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member who got the item"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "item",
+            "The name of the collection log item"
+        )
+        .required(true)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Attachment,
+            "screenshot",
+            "Screenshot of the item"
+        )
+        .required(false)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("admin_clog_remove")
+        .description("ADMIN: Remove a collection log entry from another member")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member to remove the entry from"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "id",
+            "The ID of the collection log entry to remove (leave empty to see their recent entries)"
+        )
+        .required(false)
+        .set_autocomplete(true)
+        .min_int_value(1)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("points_adjust")
+        .description("ADMIN: Give or take points from a member, e.g. event prizes or penalties")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The member to adjust"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "amount",
+            "Points to add, negative to take points away"
+        )
+        .required(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "reason",
+            "Why, shown in the member's history"
+        )
+        .required(true)
+        .max_length(points_adjust::MAX_REASON_LENGTH)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("backfill")
+        .description("ADMIN: Auto-track RuneLite channel messages missed while the bot was offline")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "since",
+            "Date to start from (YYYY-MM-DD), messages already tracked are skipped"
+        )
+        .required(true)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("unlinked")
+        .description("ADMIN: List RuneLite notifications for RS names nobody has linked")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "rsname",
+            "Show one RS name's notifications in full"
+        )
+        .required(false)
+        .set_autocomplete(true))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "dismiss",
+            "Drop the RS name's notifications instead, e.g. for someone who isn't a member"
+        )
+        .required(false)))
+    .await?;
+
+    Command::create_global_command(&ctx.http, CreateCommand::new("message_lookup")
+        .description("ADMIN: Show what auto-tracking recorded from a RuneLite message")
+        .default_member_permissions(admin_permission_set)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "message",
+            "Message ID or link"
+        )
+        .required(true)))
+    .await?;
+
     Ok(())
 }
 
@@ -166,21 +561,33 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction, db: &S
     match interaction {
         Interaction::Command(command) => {
             match command.data.name.as_str() {
-                "drop" => handle_drop(command, ctx, db).await?,
-                "drop_remove" => handle_drop_remove(command, ctx, db).await?,
-                "clog" => handle_clog(command, ctx, db).await?,
-                "clog_remove" => handle_clog_remove(command, ctx, db).await?,
+                "drop" | "admin_drop" => handle_drop(command, ctx, db).await?,
+                "drop_remove" | "admin_drop_remove" => handle_drop_remove(command, ctx, db).await?,
+                "clog" | "admin_clog" => handle_clog(command, ctx, db).await?,
+                "clog_remove" | "admin_clog_remove" => handle_clog_remove(command, ctx, db).await?,
+                "pet" => handle_pet(command, ctx, db).await?,
                 "points" => handle_points(command, ctx, db).await?,
+                "points_history" => handle_points_history(command, ctx, db).await?,
+                "points_adjust" => handle_points_adjust(command, ctx, db).await?,
+                "unlinked" => handle_unlinked(command, ctx, db).await?,
+                "backfill" => handle_backfill(command, ctx, db).await?,
+                "message_lookup" => handle_message_lookup(command, ctx, db).await?,
                 "leaderboard" => handle_leaderboard(command, ctx, db).await?,
                 "stats" => handle_stats(command, ctx, db).await?,
+                "price" => handle_price(command, ctx).await?,
                 "rsname" => handle_rsname(command, ctx, db).await?,
                 "rsname_remove" => handle_rsname_remove(command, ctx, db).await?,
                 "rsnames" => handle_rsnames(command, ctx, db).await?,
                 "recalculate" => handle_recalculate(command, ctx, db).await?,
+                "recalculate_undo" => handle_recalculate_undo(command, ctx, db).await?,
                 "clamp" => handle_clamp(command, ctx, db, true).await?,
                 "unclamp" => handle_clamp(command, ctx, db, false).await?,
                 "whitelist" => handle_whitelist(command, ctx, db, true).await?,
                 "unwhitelist" => handle_whitelist(command, ctx, db, false).await?,
+                "rank_sync" => handle_rank_sync(command, ctx, db).await?,
+                "scoring" => handle_scoring(command, ctx, db).await?,
+                "scoring_set" => handle_scoring_set(command, ctx, db).await?,
+                "scoring_override" => handle_scoring_override(command, ctx, db).await?,
                 _ => {
                     error!("Unknown command: {}", command.data.name);
                 }
@@ -188,12 +595,79 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction, db: &S
         }
         Interaction::Autocomplete(autocomplete) => {
             match autocomplete.data.name.as_str() {
-                "drop" | "clog" | "whitelist" | "unwhitelist" => {
+                "drop" | "admin_drop" | "leaderboard" if autocomplete.data.autocomplete().is_some_and(|opt| opt.name == "source") => {
+                    let partial = autocomplete.data.autocomplete().map_or("", |opt| opt.value);
+                    let pattern = format!("%{}%", partial);
+
+                    // Sources already seen on drops, plus the collection log categories
+                    let sources = sqlx::query_scalar!(
+                        r#"SELECT source AS "source!: String" FROM (
+                             SELECT source FROM drops WHERE source IS NOT NULL
+                             UNION
+                             SELECT category AS source FROM category_table
+                           )
+                           WHERE source LIKE ?
+                           ORDER BY source
+                           LIMIT 25"#,
+                        pattern
+                    )
+                    .fetch_all(db)
+                    .await?;
+
+                    let choices: Vec<AutocompleteChoice> = sources
+                        .into_iter()
+                        .map(|source| AutocompleteChoice::new(source.clone(), source))
+                        .collect();
+
+                    autocomplete.create_response(&ctx.http,
+                        CreateInteractionResponse::Autocomplete(
+                            CreateAutocompleteResponse::new().set_choices(choices)
+                        )
+                    ).await?;
+                }
+                "leaderboard" if autocomplete.data.autocomplete().is_some_and(|opt| opt.name == "item") => {
+                    let partial = autocomplete.data.autocomplete().map_or("", |opt| opt.value);
+                    let pattern = format!("%{}%", partial);
+
+                    // Only suggest items somebody has actually recorded
+                    let items = sqlx::query_scalar!(
+                        r#"SELECT item_name AS "item_name!: String" FROM (
+                             SELECT item_name FROM drops
+                             UNION
+                             SELECT item_name FROM collection_log_entries
+                           )
+                           WHERE item_name LIKE ?
+                           ORDER BY item_name
+                           LIMIT 25"#,
+                        pattern
+                    )
+                    .fetch_all(db)
+                    .await?;
+
+                    let choices: Vec<AutocompleteChoice> = items
+                        .into_iter()
+                        .map(|item| AutocompleteChoice::new(item.clone(), item))
+                        .collect();
+
+                    autocomplete.create_response(&ctx.http,
+                        CreateInteractionResponse::Autocomplete(
+                            CreateAutocompleteResponse::new().set_choices(choices)
+                        )
+                    ).await?;
+                }
+                "drop" | "admin_drop" | "price" | "clog" | "admin_clog" | "whitelist" | "unwhitelist" | "scoring_override" => {
                     if let Some(option) = autocomplete.data.options.iter().find(|opt| opt.name == "item" && opt.value.as_str().is_some()) {
                         if let Some(partial) = option.value.as_str() {
                             let data = ctx.data.read().await;
                             
-                            let suggestions = if autocomplete.data.name == "drop" {
+                            let wants_drop_items = match autocomplete.data.name.as_str() {
+                                "drop" | "admin_drop" | "price" => true,
+                                "scoring_override" => autocomplete.data.options.iter()
+                                    .any(|opt| opt.name == "type" && opt.value.as_str() == Some("drop")),
+                                _ => false,
+                            };
+
+                            let suggestions = if wants_drop_items {
                                 // Get price manager for drop suggestions
                                 if let Some(price_manager) = data.get::<PriceManagerKey>() {
                                     price_manager.get_item_suggestions(partial).await
@@ -222,11 +696,62 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction, db: &S
                         }
                     }
                 }
-                "drop_remove" | "clog_remove" => {
-                    if let Some(option) = autocomplete.data.options.iter().find(|opt| opt.name == "id") {
-                        let discord_id = autocomplete.user.id.to_string();
+                "unlinked" => {
+                    let partial = autocomplete.data.autocomplete().map_or("", |opt| opt.value);
+                    let pattern = format!("%{}%", partial);
+
+                    let names = sqlx::query_scalar!(
+                        r#"SELECT rs_name AS "rs_name!: String" FROM unlinked_events
+                           WHERE status = 'pending' AND rs_name LIKE ?
+                           GROUP BY rs_name COLLATE NOCASE
+                           ORDER BY rs_name COLLATE NOCASE
+                           LIMIT 25"#,
+                        pattern
+                    )
+                    .fetch_all(db)
+                    .await?;
+
+                    let choices: Vec<AutocompleteChoice> = names
+                        .into_iter()
+                        .map(|name| AutocompleteChoice::new(name.clone(), name))
+                        .collect();
+
+                    autocomplete.create_response(&ctx.http,
+                        CreateInteractionResponse::Autocomplete(
+                            CreateAutocompleteResponse::new().set_choices(choices)
+                        )
+                    ).await?;
+                }
+                "pet" => {
+                    let partial = autocomplete.data.autocomplete().map_or("", |opt| opt.value);
+                    let data = ctx.data.read().await;
+
+                    let suggestions = match data.get::<CollectionLogManagerKey>() {
+                        Some(clog_manager) => clog_manager.get_pet_suggestions(partial).await,
+                        None => Vec::new(),
+                    };
+
+                    let choices: Vec<AutocompleteChoice> = suggestions
+                        .into_iter()
+                        .map(|pet| AutocompleteChoice::new(pet.clone(), pet))
+                        .collect();
+
+                    autocomplete.create_response(&ctx.http,
+                        CreateInteractionResponse::Autocomplete(
+                            CreateAutocompleteResponse::new().set_choices(choices)
+                        )
+                    ).await?;
+                }
+                "drop_remove" | "clog_remove" | "admin_drop_remove" | "admin_clog_remove" => {
+                    if let Some(_option) = autocomplete.data.options.iter().find(|opt| opt.name == "id") {
+                        // The admin variants list the chosen member's entries
+                        let discord_id = autocomplete.data.options.iter()
+                            .find(|opt| opt.name == "user")
+                            .and_then(|opt| opt.value.as_user_id())
+                            .unwrap_or(autocomplete.user.id)
+                            .to_string();
                         
-                        let recent_items = if autocomplete.data.name == "drop_remove" {
+                        let recent_items = if autocomplete.data.name.ends_with("drop_remove") {
                             // Get recent drops
                             sqlx::query!(
                                 "SELECT id, item_name, quantity, timestamp FROM drops 
@@ -279,7 +804,35 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction, db: &S
                         ).await?;
                     }
                 }
-                "clamp" | "unclamp" => {
+                "recalculate_undo" => {
+                    // Most recent batches that can still be undone
+                    let batches = sqlx::query!(
+                        r#"SELECT b.id AS "id!: i64", b.timestamp, COUNT(c.id) AS "entries!: i64"
+                           FROM recalculation_batches b
+                           LEFT JOIN recalculation_changes c ON c.batch_id = b.id
+                           WHERE b.undone_at IS NULL
+                           GROUP BY b.id
+                           ORDER BY b.id DESC
+                           LIMIT 25"#
+                    )
+                    .fetch_all(db)
+                    .await?;
+
+                    let choices: Vec<AutocompleteChoice> = batches
+                        .into_iter()
+                        .map(|row| {
+                            let timestamp = row.timestamp.unwrap_or_default();
+                            AutocompleteChoice::new(format!("#{}: {} records ({})", row.id, row.entries, timestamp), row.id)
+                        })
+                        .collect();
+
+                    autocomplete.create_response(&ctx.http,
+                        CreateInteractionResponse::Autocomplete(
+                            CreateAutocompleteResponse::new().set_choices(choices)
+                        )
+                    ).await?;
+                }
+                "clamp" | "unclamp" | "leaderboard" => {
                     if let Some(option) = autocomplete.data.options.iter().find(|opt| opt.name == "category" && opt.value.as_str().is_some()) {
                         if let Some(partial) = option.value.as_str() {
                             let data = ctx.data.read().await;
@@ -308,6 +861,12 @@ pub async fn handle_interaction(ctx: &Context, interaction: &Interaction, db: &S
                 _ => {}
             }
         }
+        Interaction::Component(component) if component.data.custom_id.starts_with("submission_") => {
+            handle_submission_review(component, ctx, db).await?;
+        }
+        Interaction::Component(component) if component.data.custom_id.starts_with("split_join_") => {
+            crate::splits::handle_join_review(component, ctx, db).await?;
+        }
         _ => {}
     }
     
@@ -1,13 +1,25 @@
 use std::env;
-use serenity::all::RoleId;
-use serenity::model::id::ChannelId;
+use std::path::PathBuf;
+use serenity::model::id::{ChannelId, GuildId};
 use serenity::prelude::TypeMapKey;
 
+pub const DEFAULT_PRICES_API_URL: &str = "https://prices.runescape.wiki/api/v1/osrs";
+pub const DEFAULT_WIKI_API_URL: &str = "https://oldschool.runescape.wiki/api.php";
+
 pub struct Config {
     pub mod_channel_id: ChannelId,
     pub log_channel_id: ChannelId,
     pub runelite_channel_id: Option<ChannelId>,
     pub rank_request_channel_id: Option<ChannelId>,
+    pub guild_id: Option<GuildId>,
+    /// Base URL of the OSRS Wiki real-time prices API, overridable to use a local stand-in
+    pub prices_api_url: String,
+    /// The OSRS Wiki MediaWiki `api.php` endpoint used for the collection log table
+    pub wiki_api_url: String,
+    /// Manual /drop and /clog need a screenshot and a mod's approval before they award points
+    pub require_approval: bool,
+    /// Where to keep downloaded copies of drop and clog screenshots, only their URLs are kept if unset
+    pub screenshot_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -40,11 +52,41 @@ impl Config {
             Err(_) => None
         };
 
+        // Optional guild ID, enables automatic rank role assignment
+        let guild_id = match env::var("GUILD_ID") {
+            Ok(id) => match id.parse::<u64>() {
+                Ok(id) => Some(GuildId::new(id)),
+                Err(_) => None
+            },
+            Err(_) => None
+        };
+
+        let prices_api_url = env::var("PRICES_API_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| DEFAULT_PRICES_API_URL.to_string());
+
+        let wiki_api_url = env::var("WIKI_API_URL")
+            .unwrap_or_else(|_| DEFAULT_WIKI_API_URL.to_string());
+
+        let require_approval = env::var("REQUIRE_APPROVAL")
+            .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        let screenshot_dir = env::var("SCREENSHOT_DIR")
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+
         Ok(Self {
             mod_channel_id: ChannelId::new(mod_channel_id),
             log_channel_id: ChannelId::new(log_channel_id),
             runelite_channel_id,
             rank_request_channel_id,
+            guild_id,
+            prices_api_url,
+            wiki_api_url,
+            require_approval,
+            screenshot_dir,
         })
     }
 }
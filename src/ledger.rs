@@ -0,0 +1,113 @@
+use anyhow::Result;
+use sqlx::{Executor, Sqlite};
+
+/// Why a member's points changed, stored in `point_transactions.reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Drop,
+    DropRemoved,
+    /// A share of a split drop shrinking because another teammate joined it
+    DropSplit,
+    Clog,
+    ClogRemoved,
+    Pet,
+    Recalculation,
+    /// Reverses one entry of a recalculation batch
+    RecalculationUndo,
+    /// Written by the audit binary to bring the ledger back in line with the drop and clog rows
+    AuditCorrection,
+    /// An admin's /points_adjust, the reason given is kept in `point_adjustments`
+    Adjustment,
+}
+
+impl Reason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reason::Drop => "drop",
+            Reason::DropRemoved => "drop_removed",
+            Reason::DropSplit => "drop_split",
+            Reason::Clog => "clog",
+            Reason::ClogRemoved => "clog_removed",
+            Reason::Pet => "pet",
+            Reason::Recalculation => "recalculation",
+            Reason::RecalculationUndo => "recalculation_undo",
+            Reason::AuditCorrection => "audit_correction",
+            Reason::Adjustment => "adjustment",
+        }
+    }
+}
+
+/// Human readable name for a stored reason, including ones from before a variant existed
+pub fn describe_reason(reason: &str) -> &str {
+    match reason {
+        "drop" => "Drops",
+        "drop_removed" => "Removed drops",
+        "drop_split" => "Shared with teammates",
+        "clog" => "Collection log",
+        "clog_removed" => "Removed collection log entries",
+        "pet" => "Pets",
+        "recalculation" => "Recalculations",
+        "recalculation_undo" => "Undone recalculations",
+        "audit_correction" => "Audit corrections",
+        "adjustment" => "Manual adjustments",
+        "opening_balance" => "Before the ledger",
+        other => other,
+    }
+}
+
+/// One line for the ledger
+#[derive(Debug, Clone)]
+pub struct PointChange<'a> {
+    pub delta: i64,
+    pub reason: Reason,
+    /// The row the points came from, as (table, id)
+    pub source: Option<(&'static str, i64)>,
+    /// Discord ID of whoever made the change, `None` for automatic tracking
+    pub actor_id: Option<&'a str>,
+}
+
+impl<'a> PointChange<'a> {
+    pub fn new(delta: i64, reason: Reason) -> Self {
+        Self {
+            delta,
+            reason,
+            source: None,
+            actor_id: None,
+        }
+    }
+
+    pub fn source(mut self, table: &'static str, id: i64) -> Self {
+        self.source = Some((table, id));
+        self
+    }
+
+    pub fn actor(mut self, actor_id: &'a str) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+}
+
+/// Appends the change to `point_transactions`; the database triggers apply it to `users.points`
+pub async fn record<'e, E>(executor: E, discord_id: &str, change: &PointChange<'_>) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let reason = change.reason.as_str();
+    let source_table = change.source.map(|(table, _)| table);
+    let source_id = change.source.map(|(_, id)| id);
+
+    sqlx::query!(
+        "INSERT INTO point_transactions (discord_id, delta, reason, source_table, source_id, actor_id)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        discord_id,
+        change.delta,
+        reason,
+        source_table,
+        source_id,
+        change.actor_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
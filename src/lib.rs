@@ -0,0 +1,2 @@
+pub mod parsers;
+pub mod ledger;
@@ -5,6 +5,12 @@ mod config;
 mod rank_manager;
 mod logger;
 mod runescape_tracker;
+mod api_cache;
+mod scoring;
+mod screenshots;
+mod splits;
+mod processed_messages;
+mod unlinked;
 
 use anyhow::Result;
 use serenity::all::{
@@ -12,6 +18,7 @@ use serenity::all::{
     Interaction,
     Ready,
     Message,
+    Reaction,
 };
 use serenity::async_trait;
 use serenity::prelude::*;
@@ -19,17 +26,22 @@ use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::SqlitePool;
 use std::env;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use dotenvy::dotenv;
-use tracing::{error, info, debug};
+use tracing::{error, info};
 use command_handler::{PriceManagerKey, CollectionLogManagerKey};
 use config::{Config, ConfigKey};
 use runescape_tracker::RunescapeTrackerKey;
+// Shared with the audit binary, so it lives in the library
+use kittyscape_loot_bot::ledger;
 
 struct Handler {
     db: SqlitePool,
     price_manager: Arc<prices::PriceManager>,
     collection_log_manager: Arc<collection_log::CollectionLogManager>,
     runescape_tracker: Arc<runescape_tracker::RunescapeTracker>,
+    /// Set by the first `ready`, reconnects fire it again and mustn't start a second set of background tasks
+    started: AtomicBool,
 }
 
 #[async_trait]
@@ -42,14 +54,20 @@ impl EventHandler for Handler {
 
     async fn message(&self, ctx: Context, msg: Message) {
         // We only care about messages in the RuneLite plugin channel
-        let data = ctx.data.read().await;
-        if let Some(config) = data.get::<ConfigKey>() {
-            if let Some(runelite_channel_id) = config.runelite_channel_id {
-                if msg.channel_id == runelite_channel_id && msg.author.bot {
-                    if let Err(why) = self.runescape_tracker.process_message(&ctx, &msg, &self.db).await {
-                        error!("Error processing RuneLite message: {:?}", why);
-                    }
-                }
+        let runelite_channel_id = ctx.data.read().await.get::<ConfigKey>().and_then(|config| config.runelite_channel_id);
+        if runelite_channel_id == Some(msg.channel_id) && runescape_tracker::is_runelite_message(&ctx, &msg) {
+            if let Err(why) = self.runescape_tracker.process_message(&ctx, &msg, &self.db).await {
+                error!("Error processing RuneLite message: {:?}", why);
+            }
+        }
+    }
+
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        // Teammates asking to join an auto-tracked drop from the RuneLite channel
+        let runelite_channel_id = ctx.data.read().await.get::<ConfigKey>().and_then(|config| config.runelite_channel_id);
+        if runelite_channel_id == Some(reaction.channel_id) {
+            if let Err(why) = self.runescape_tracker.process_reaction(&ctx, &reaction, &self.db).await {
+                error!("Error processing RuneLite reaction: {:?}", why);
             }
         }
     }
@@ -70,8 +88,25 @@ impl EventHandler for Handler {
             error!("Error registering commands: {:?}", why);
         }
 
-        // Start price updates
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // Start price and collection log updates
         Arc::clone(&self.price_manager).start_price_updates().await;
+        Arc::clone(&self.collection_log_manager).start_updates().await;
+
+        // Pick up whatever was posted in the RuneLite channel while the bot was offline
+        let runelite_channel_id = ctx.data.read().await.get::<ConfigKey>().and_then(|config| config.runelite_channel_id);
+        if let Some(channel_id) = runelite_channel_id {
+            let runescape_tracker = Arc::clone(&self.runescape_tracker);
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                if let Err(why) = runescape_tracker.catch_up(&ctx, &db, channel_id).await {
+                    error!("Error catching up on the RuneLite channel: {:?}", why);
+                }
+            });
+        }
     }
 }
 
@@ -101,9 +136,15 @@ async fn main() -> Result<()> {
     // Run migrations
     sqlx::migrate!().run(&db).await?;
 
+    // Messages the last run was still processing when it stopped, the startup catch-up retries them
+    let expired = processed_messages::expire_claims(&db).await?;
+    if expired > 0 {
+        info!("{} RuneLite messages were still being processed when the bot stopped, they'll be retried", expired);
+    }
+
     // Initialize managers
-    let price_manager = Arc::new(prices::PriceManager::new().await?);
-    let collection_log_manager = Arc::new(collection_log::CollectionLogManager::new(&db).await?);
+    let price_manager = Arc::new(prices::PriceManager::new(&db, &config.prices_api_url).await?);
+    let collection_log_manager = Arc::new(collection_log::CollectionLogManager::new(&db, &config.wiki_api_url).await?);
     let runescape_tracker = Arc::new(runescape_tracker::RunescapeTracker::new().await?);
 
     // Create a new instance of the client
@@ -114,6 +155,7 @@ async fn main() -> Result<()> {
             price_manager: Arc::clone(&price_manager),
             collection_log_manager: Arc::clone(&collection_log_manager),
             runescape_tracker: Arc::clone(&runescape_tracker),
+            started: AtomicBool::new(false),
         })
         .await?;
 
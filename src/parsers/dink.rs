@@ -0,0 +1,206 @@
+use serde::Deserialize;
+use serde_json::Value;
+use serenity::all::{Attachment, Message};
+use serenity::async_trait;
+use std::collections::HashMap;
+use tracing::{debug, warn};
+use super::{ClogEvent, DropEvent, LevelEvent, NotificationParser, ParsedEvent, PetEvent};
+
+// The Dink RuneLite plugin posts its notifications as a webhook with a structured JSON payload.
+// Example payload (trimmed):
+// {
+//   "type": "LOOT",
+//   "playerName": "Zezima",
+//   "extra": {
+//     "items": [{ "id": 11832, "quantity": 1, "priceEach": 12000000, "name": "Bandos chestplate" }],
+//     "source": "General Graardor"
+//   }
+// }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkPayload {
+    #[serde(rename = "type")]
+    pub notification_type: String,
+    pub player_name: String,
+    #[serde(default)]
+    pub extra: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkItem {
+    pub id: i64,
+    pub quantity: i64,
+    pub price_each: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkLoot {
+    pub items: Vec<DinkItem>,
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkCollection {
+    pub item_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkPet {
+    pub pet_name: Option<String>,
+    #[serde(default)]
+    pub duplicate: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DinkLevel {
+    pub levelled_skills: HashMap<String, i64>,
+}
+
+#[derive(Debug)]
+pub enum DinkEvent {
+    Loot(DinkLoot),
+    Collection(DinkCollection),
+    Pet(DinkPet),
+    Level(DinkLevel),
+    /// Any notification type we don't track (deaths, quests, diaries...)
+    Other(String),
+}
+
+impl DinkPayload {
+    /// Decodes the `extra` block according to the notification type
+    pub fn event(&self) -> serde_json::Result<DinkEvent> {
+        let extra = self.extra.clone();
+        Ok(match self.notification_type.as_str() {
+            "LOOT" => DinkEvent::Loot(serde_json::from_value(extra)?),
+            "COLLECTION" => DinkEvent::Collection(serde_json::from_value(extra)?),
+            "PET" => DinkEvent::Pet(serde_json::from_value(extra)?),
+            "LEVEL" => DinkEvent::Level(serde_json::from_value(extra)?),
+            other => DinkEvent::Other(other.to_string()),
+        })
+    }
+}
+
+/// Parses a Dink payload from raw text, accepting either bare JSON or a ```json code block
+pub fn parse_payload(text: &str) -> Option<DinkPayload> {
+    let text = text.trim();
+    let json = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|s| s.strip_suffix("```"))
+        .unwrap_or(text)
+        .trim();
+
+    if !json.starts_with('{') {
+        return None;
+    }
+
+    serde_json::from_str::<DinkPayload>(json).ok()
+}
+
+/// Dink payloads are a few KB at most, anything much bigger isn't one and isn't worth downloading
+pub const MAX_ATTACHMENT_BYTES: u32 = 64 * 1024;
+
+/// Whether an attachment could hold a Dink payload: a small `.json` file that Discord doesn't say is something else
+fn is_payload_attachment(attachment: &Attachment) -> bool {
+    if !attachment.filename.to_lowercase().ends_with(".json") {
+        return false;
+    }
+    if attachment.size > MAX_ATTACHMENT_BYTES {
+        debug!("Skipping attachment {}, {} bytes is too big for a Dink payload", attachment.filename, attachment.size);
+        return false;
+    }
+    match attachment.content_type.as_deref() {
+        Some(content_type) if !content_type.starts_with("application/json") && !content_type.starts_with("text/plain") => {
+            debug!("Skipping attachment {}, its content type is {}", attachment.filename, content_type);
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Looks for a Dink payload on a message: a `.json` attachment first, then the content and embed descriptions
+pub async fn find_payload(msg: &Message) -> Option<DinkPayload> {
+    for attachment in msg.attachments.iter().filter(|attachment| is_payload_attachment(attachment)) {
+        debug!("Found JSON attachment: {}", attachment.filename);
+        match attachment.download().await {
+            // The declared size is Discord's, so the download is checked as well
+            Ok(bytes) if bytes.len() > MAX_ATTACHMENT_BYTES as usize => {
+                warn!("Attachment {} was {} bytes, too big for a Dink payload", attachment.filename, bytes.len());
+            }
+            Ok(bytes) => match serde_json::from_slice::<DinkPayload>(&bytes) {
+                Ok(payload) => return Some(payload),
+                Err(why) => debug!("Attachment {} is not a Dink payload: {:?}", attachment.filename, why),
+            },
+            Err(why) => warn!("Failed to download attachment {}: {:?}", attachment.filename, why),
+        }
+    }
+
+    if let Some(payload) = parse_payload(&msg.content) {
+        return Some(payload);
+    }
+
+    msg.embeds
+        .iter()
+        .filter_map(|embed| embed.description.as_deref())
+        .find_map(parse_payload)
+}
+
+/// Structured notifications from the Dink plugin
+pub struct DinkParser;
+
+#[async_trait]
+impl NotificationParser for DinkParser {
+    fn name(&self) -> &'static str {
+        "dink"
+    }
+
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>> {
+        let payload = find_payload(msg).await?;
+        let rs_name = payload.player_name.trim().to_string();
+
+        let event = match payload.event() {
+            Ok(event) => event,
+            Err(why) => {
+                warn!("Could not decode Dink {} payload: {:?}", payload.notification_type, why);
+                return None;
+            }
+        };
+
+        let events = match event {
+            DinkEvent::Loot(loot) => loot.items
+                .into_iter()
+                .map(|item| ParsedEvent::Drop(DropEvent {
+                    rs_name: rs_name.clone(),
+                    value: item.price_each * item.quantity,
+                    item_name: item.name,
+                    item_id: Some(item.id),
+                    quantity: item.quantity,
+                    source: Some(loot.source.clone()),
+                }))
+                .collect(),
+            DinkEvent::Collection(clog) => vec![ParsedEvent::Clog(ClogEvent {
+                rs_name,
+                item_name: clog.item_name,
+            })],
+            DinkEvent::Pet(pet) => vec![ParsedEvent::Pet(PetEvent {
+                rs_name,
+                pet_name: pet.pet_name,
+                duplicate: pet.duplicate,
+            })],
+            DinkEvent::Level(level) => vec![ParsedEvent::Level(LevelEvent {
+                rs_name,
+                skills: level.levelled_skills,
+            })],
+            DinkEvent::Other(kind) => vec![ParsedEvent::Ignored { rs_name, kind }],
+        };
+
+        Some(events)
+    }
+}
@@ -0,0 +1,81 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::all::Message;
+use serenity::async_trait;
+use tracing::debug;
+use super::{DropEvent, NotificationParser, ParsedEvent};
+
+lazy_static! {
+    // Regex for parsing embed drop notifications
+    // This pattern handles formats like:
+    // "Just got [Coal] from [Monster]"
+    // "Just got 5x [Coal] from [Monster]"
+    // "Just got [Coal](url) from [Monster](url)"
+    // "Just got 5x [Coal](url) from lvl 98 [Monster](url)"
+    pub static ref EMBED_DROP_REGEX: Regex = Regex::new(r"Just got (?:(\d+)x\s+)?\[(.+?)(?:\]\(.+?\)|\])\s+from(?:\s+lvl\s+\d+)?\s+\[(.+?)(?:\]\(.+?\)|\])").unwrap();
+    pub static ref EMBED_VALUE_REGEX: Regex = Regex::new(r"```fix\s*([0-9,]+) GP\s*```").unwrap();
+}
+
+/// Embeds from the Discord Rare Drop Notificater plugin: the player is the embed author,
+/// the drop is in the description and the value is in a "GE Value" field
+pub struct EmbedDropParser;
+
+#[async_trait]
+impl NotificationParser for EmbedDropParser {
+    fn name(&self) -> &'static str {
+        "embed drop"
+    }
+
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>> {
+        for (i, embed) in msg.embeds.iter().enumerate() {
+            debug!("Processing embed #{}", i + 1);
+
+            let Some(description) = &embed.description else {
+                debug!("Embed has no description");
+                continue;
+            };
+
+            let Some(captures) = EMBED_DROP_REGEX.captures(description) else {
+                debug!("Could not find drop pattern in embed description");
+                continue;
+            };
+
+            let item_name = captures.get(2).map_or("", |m| m.as_str()).trim();
+            let source = captures.get(3).map_or("", |m| m.as_str()).trim();
+
+            // Parse quantity - default to 1 if not present
+            let quantity: i64 = captures.get(1).map_or("1", |m| m.as_str()).parse().unwrap_or(1);
+
+            // Try to extract the value from the GE Value field
+            let mut value: i64 = 0;
+            for field in &embed.fields {
+                if field.name == "GE Value" && !field.value.is_empty() {
+                    if let Some(value_capture) = EMBED_VALUE_REGEX.captures(&field.value) {
+                        let value_str = value_capture.get(1).map_or("0", |m| m.as_str()).replace(',', "");
+                        value = value_str.parse().unwrap_or(0);
+                    }
+                }
+            }
+
+            // The message author is just the webhook, so the RS name has to come from the embed author
+            let rs_name = match &embed.author {
+                Some(author) if !author.name.is_empty() => author.name.clone(),
+                _ => {
+                    debug!("No RS name found in embed author for item: {}", item_name);
+                    return None;
+                }
+            };
+
+            return Some(vec![ParsedEvent::Drop(DropEvent {
+                rs_name,
+                item_name: item_name.to_string(),
+                item_id: None,
+                quantity,
+                value,
+                source: Some(source.to_string()).filter(|s| !s.is_empty()),
+            })]);
+        }
+
+        None
+    }
+}
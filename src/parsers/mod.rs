@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use serenity::all::Message;
+use serenity::async_trait;
+use std::collections::HashMap;
+
+pub mod dink;
+pub mod embed;
+pub mod text;
+
+pub use dink::DinkParser;
+pub use embed::EmbedDropParser;
+pub use text::{TextClogParser, TextDropParser, TextPetParser};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DropEvent {
+    pub rs_name: String,
+    pub item_name: String,
+    pub item_id: Option<i64>,
+    pub quantity: i64,
+    /// Total value of the stack in gp
+    pub value: i64,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClogEvent {
+    pub rs_name: String,
+    pub item_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PetEvent {
+    pub rs_name: String,
+    pub pet_name: Option<String>,
+    #[serde(default)]
+    pub duplicate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelEvent {
+    pub rs_name: String,
+    #[serde(default)]
+    pub skills: HashMap<String, i64>,
+}
+
+/// Something a RuneLite plugin told us happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ParsedEvent {
+    Drop(DropEvent),
+    Clog(ClogEvent),
+    Pet(PetEvent),
+    Level(LevelEvent),
+    /// A recognised notification we don't track (deaths, quests, diaries...)
+    Ignored { rs_name: String, kind: String },
+}
+
+impl ParsedEvent {
+    pub fn rs_name(&self) -> &str {
+        match self {
+            ParsedEvent::Drop(drop) => &drop.rs_name,
+            ParsedEvent::Clog(clog) => &clog.rs_name,
+            ParsedEvent::Pet(pet) => &pet.rs_name,
+            ParsedEvent::Level(level) => &level.rs_name,
+            ParsedEvent::Ignored { rs_name, .. } => rs_name,
+        }
+    }
+}
+
+/// One message format from one RuneLite plugin
+#[async_trait]
+pub trait NotificationParser: Send + Sync {
+    /// Short name used in logs and by the analyzer
+    fn name(&self) -> &'static str;
+
+    /// Returns the events in the message, or `None` if the message isn't in this parser's format
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>>;
+}
+
+/// The parsers tried, in order, against every message in the RuneLite channel
+pub struct ParserRegistry {
+    parsers: Vec<Box<dyn NotificationParser>>,
+}
+
+impl ParserRegistry {
+    pub fn empty() -> Self {
+        Self { parsers: Vec::new() }
+    }
+
+    pub fn register(&mut self, parser: impl NotificationParser + 'static) {
+        self.parsers.push(Box::new(parser));
+    }
+
+    pub fn parsers(&self) -> &[Box<dyn NotificationParser>] {
+        &self.parsers
+    }
+
+    /// Runs the message through each parser in turn and returns the first match along with the parser's name
+    pub async fn parse(&self, msg: &Message) -> Option<(&'static str, Vec<ParsedEvent>)> {
+        for parser in &self.parsers {
+            if let Some(events) = parser.parse(msg).await {
+                return Some((parser.name(), events));
+            }
+        }
+        None
+    }
+}
+
+impl Default for ParserRegistry {
+    /// Every format the bot understands. Structured formats go first so they win over text scraping.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(DinkParser);
+        registry.register(EmbedDropParser);
+        registry.register(TextDropParser);
+        registry.register(TextClogParser);
+        registry.register(TextPetParser);
+        registry
+    }
+}
@@ -0,0 +1,106 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::all::Message;
+use serenity::async_trait;
+use super::{ClogEvent, DropEvent, NotificationParser, ParsedEvent, PetEvent};
+
+lazy_static! {
+    // Regular expressions for parsing plain text messages from RuneLite plugins
+    pub static ref DROP_REGEX: Regex = Regex::new(r"^(.+) received: (.+?)(?: \((\d+)x\))? \(([0-9,]+) coins\)$").unwrap();
+    pub static ref CLOG_REGEX: Regex = Regex::new(r"(?:\*\*(.+)\*\*\s+New item added to your collection log: \*\*(.+)\*\*|^(.+) received a collection log item: (.+)$)").unwrap();
+    // The game messages for a pet drop, relayed with the player's name in front. "would have been followed" means they already had it
+    pub static ref PET_REGEX: Regex = Regex::new(r"(?i)^(?:\*\*(.+?)\*\*|(.+?)):?\s+(?:you have|has) a funny feeling like (?:you|they)(?:'re| are)? (would have been|being) followed|^(?:\*\*(.+?)\*\*|(.+?)):?\s+(?:you feel|feels) something weird sneaking into (?:your|their) backpack").unwrap();
+}
+
+/// "Player received: Item (5x) (1,234 coins)"
+pub struct TextDropParser;
+
+#[async_trait]
+impl NotificationParser for TextDropParser {
+    fn name(&self) -> &'static str {
+        "text drop"
+    }
+
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>> {
+        let captures = DROP_REGEX.captures(&msg.content)?;
+
+        let rs_name = captures.get(1).map_or("", |m| m.as_str()).trim();
+        let item_name = captures.get(2).map_or("", |m| m.as_str()).trim();
+        let quantity: i64 = captures.get(3)
+            .map_or("1", |m| m.as_str())
+            .parse()
+            .unwrap_or(1);
+        let value_str = captures.get(4).map_or("0", |m| m.as_str()).replace(',', "");
+        let value: i64 = value_str.parse().unwrap_or(0);
+
+        Some(vec![ParsedEvent::Drop(DropEvent {
+            rs_name: rs_name.to_string(),
+            item_name: item_name.to_string(),
+            item_id: None,
+            quantity,
+            value,
+            source: None,
+        })])
+    }
+}
+
+/// "**Player** New item added to your collection log: **Item**" (Discord Collection Logger)
+/// or "Player received a collection log item: Item"
+pub struct TextClogParser;
+
+#[async_trait]
+impl NotificationParser for TextClogParser {
+    fn name(&self) -> &'static str {
+        "text clog"
+    }
+
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>> {
+        let captures = CLOG_REGEX.captures(&msg.content)?;
+
+        // Try the new format first (with bold/asterisks), then fall back to the original format
+        let (rs_name, item_name) = if let (Some(name), Some(item)) = (captures.get(1), captures.get(2)) {
+            (name.as_str().trim(), item.as_str().trim())
+        } else if let (Some(name), Some(item)) = (captures.get(3), captures.get(4)) {
+            (name.as_str().trim(), item.as_str().trim())
+        } else {
+            return None;
+        };
+
+        if rs_name.is_empty() || item_name.is_empty() {
+            return None;
+        }
+
+        Some(vec![ParsedEvent::Clog(ClogEvent {
+            rs_name: rs_name.to_string(),
+            item_name: item_name.to_string(),
+        })])
+    }
+}
+
+/// "**Player** You have a funny feeling like you're being followed." and the other pet messages.
+/// They never say which pet it was
+pub struct TextPetParser;
+
+#[async_trait]
+impl NotificationParser for TextPetParser {
+    fn name(&self) -> &'static str {
+        "text pet"
+    }
+
+    async fn parse(&self, msg: &Message) -> Option<Vec<ParsedEvent>> {
+        let captures = PET_REGEX.captures(msg.content.trim())?;
+
+        let rs_name = [1, 2, 4, 5]
+            .iter()
+            .find_map(|&i| captures.get(i))
+            .map(|m| m.as_str().trim())
+            .filter(|name| !name.is_empty())?;
+        let duplicate = captures.get(3).is_some_and(|m| m.as_str().eq_ignore_ascii_case("would have been"));
+
+        Some(vec![ParsedEvent::Pet(PetEvent {
+            rs_name: rs_name.to_string(),
+            pet_name: None,
+            duplicate,
+        })])
+    }
+}
@@ -1,11 +1,19 @@
 use anyhow::Result;
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
-use tracing::{error, info, debug};
+use tracing::{error, info, debug, warn};
+use crate::api_cache;
 
 const USER_AGENT: &str = "KittyScape Loot Bot/1.0";
+/// Prices are refreshed every 10 minutes but only written to `price_snapshots` this often
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+/// Snapshots older than this are thinned out to one per item per day
+const HOURLY_SNAPSHOT_DAYS: i64 = 7;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ItemMapping {
@@ -35,24 +43,53 @@ pub struct ItemPrice {
     pub low_time: Option<i64>,
 }
 
+impl ItemPrice {
+    /// The value the bot credits for one of the item: the lowest available price,
+    /// falling back to the high alch value and then 0
+    pub fn value(&self, mapping: &ItemMapping) -> i64 {
+        self.low
+            .or(self.high)
+            .or(mapping.high_alch)
+            .unwrap_or(0)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PriceData {
     pub mappings: HashMap<String, ItemMapping>,
     pub latest_prices: HashMap<i64, ItemPrice>,
+    last_snapshot: Option<Instant>,
+}
+
+/// An item's drop value at a point in time
+#[derive(Debug, Clone)]
+pub struct PriceSnapshot {
+    pub value: i64,
+    pub timestamp: NaiveDateTime,
 }
 
 pub struct PriceManager {
     data: Arc<RwLock<PriceData>>,
     client: reqwest::Client,
+    db: SqlitePool,
+    api_url: String,
 }
 
 impl PriceManager {
-    pub async fn new() -> Result<Self> {
+    /// Starts from the cached API responses if the prices API is down, and with no items at all
+    /// if there is no cache either; the mapping is retried on every price update until it loads
+    pub async fn new(db: &SqlitePool, api_url: &str) -> Result<Self> {
         let client = reqwest::Client::builder()
             .user_agent(USER_AGENT)
             .build()?;
 
-        let mappings = Self::fetch_mappings(&client).await?;
+        let mappings = match Self::fetch_mappings(&client, db, api_url).await {
+            Ok(mappings) => mappings,
+            Err(e) => {
+                error!("Failed to load item mappings, starting without prices: {}", e);
+                HashMap::new()
+            }
+        };
         info!("PriceManager initialized with {} items", mappings.len());
         
         // Debug log some example items
@@ -63,26 +100,31 @@ impl PriceManager {
         let data = PriceData {
             mappings,
             latest_prices: HashMap::new(),
+            last_snapshot: None,
         };
 
         let manager = Self {
             data: Arc::new(RwLock::new(data)),
             client,
+            db: db.clone(),
+            api_url: api_url.to_string(),
         };
 
         // Do initial price update
-        manager.update_prices().await?;
+        if let Err(e) = manager.update_prices().await {
+            error!("Failed to load prices: {}", e);
+        }
 
         Ok(manager)
     }
 
-    async fn fetch_mappings(client: &reqwest::Client) -> Result<HashMap<String, ItemMapping>> {
-        let response = client
-            .get("https://prices.runescape.wiki/api/v1/osrs/mapping")
-            .send()
-            .await?
-            .json::<Vec<ItemMapping>>()
-            .await?;
+    async fn fetch_mappings(client: &reqwest::Client, db: &SqlitePool, api_url: &str) -> Result<HashMap<String, ItemMapping>> {
+        let response: Vec<ItemMapping> = api_cache::fetch_json(
+            client,
+            db,
+            "prices_mapping",
+            &format!("{}/mapping", api_url),
+        ).await?.data;
 
         let mut mappings = HashMap::new();
         for item in response {
@@ -94,23 +136,75 @@ impl PriceManager {
     }
 
     pub async fn update_prices(&self) -> Result<()> {
-        let response = self.client
-            .get("https://prices.runescape.wiki/api/v1/osrs/latest")
-            .send()
-            .await?
-            .json::<LatestPrices>()
-            .await?;
+        if self.data.read().await.mappings.is_empty() {
+            warn!("No item mappings loaded yet, retrying");
+            let mappings = Self::fetch_mappings(&self.client, &self.db, &self.api_url).await?;
+            self.data.write().await.mappings = mappings;
+        }
+
+        let fetched: api_cache::Fetched<LatestPrices> = api_cache::fetch_json(
+            &self.client,
+            &self.db,
+            "prices_latest",
+            &format!("{}/latest", self.api_url),
+        ).await?;
 
         let mut data = self.data.write().await;
         data.latest_prices.clear();
 
-        for (id_str, price) in response.data {
+        for (id_str, price) in fetched.data.data {
             if let Ok(id) = id_str.parse::<i64>() {
                 data.latest_prices.insert(id, price);
             }
         }
 
         info!("Updated prices for {} items", data.latest_prices.len());
+
+        // Cached prices are already in the history from when they were fetched
+        let snapshot_due = !fetched.from_cache && data.last_snapshot.is_none_or(|at| at.elapsed() >= SNAPSHOT_INTERVAL);
+        let snapshot = snapshot_due.then(|| data.latest_prices.clone());
+        // Price lookups wait on the lock, so it's let go before writing the snapshot
+        drop(data);
+
+        if let Some(prices) = snapshot {
+            self.store_snapshot(&prices).await?;
+            self.data.write().await.last_snapshot = Some(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    async fn store_snapshot(&self, prices: &HashMap<i64, ItemPrice>) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        for (id, price) in prices {
+            sqlx::query!(
+                "INSERT INTO price_snapshots (item_id, high, low) VALUES (?, ?, ?)",
+                id,
+                price.high,
+                price.low
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Only the last week is kept hourly, older snapshots are thinned out to the first of each day
+        let cutoff = format!("-{} days", HOURLY_SNAPSHOT_DAYS);
+        let thinned = sqlx::query!(
+            "DELETE FROM price_snapshots
+             WHERE timestamp < datetime('now', ?1)
+               AND id NOT IN (
+                   SELECT MIN(id) FROM price_snapshots
+                   WHERE timestamp < datetime('now', ?1)
+                   GROUP BY item_id, date(timestamp)
+               )",
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+
+        info!("Stored price snapshot for {} items, thinned out {} old snapshots", prices.len(), thinned);
         Ok(())
     }
 
@@ -146,10 +240,67 @@ impl PriceManager {
         // Get the latest price
         let price = data.latest_prices.get(&mapping.id)?;
         
-        // Use the lowest available price, defaulting to high alch value if available, or 0 if not
-        Some(price.low
-            .or(price.high)
-            .or(mapping.high_alch)
-            .unwrap_or(0))
+        Some(price.value(mapping))
+    }
+
+    pub async fn get_item_mapping(&self, name: &str) -> Option<ItemMapping> {
+        self.data.read().await.mappings.get(name).cloned()
+    }
+
+    pub async fn get_latest_price(&self, name: &str) -> Option<(ItemMapping, ItemPrice)> {
+        let data = self.data.read().await;
+        let mapping = data.mappings.get(name)?;
+        let price = data.latest_prices.get(&mapping.id)?;
+        Some((mapping.clone(), price.clone()))
+    }
+
+    /// The stored snapshot nearest to `at` (UTC), in either direction
+    pub async fn get_snapshot_closest_to(&self, name: &str, at: NaiveDateTime) -> Result<Option<PriceSnapshot>> {
+        let Some(mapping) = self.get_item_mapping(name).await else {
+            return Ok(None);
+        };
+
+        let row = sqlx::query!(
+            r#"SELECT high, low, timestamp AS "timestamp!: NaiveDateTime"
+             FROM price_snapshots
+             WHERE item_id = ?
+             ORDER BY ABS(julianday(timestamp) - julianday(?))
+             LIMIT 1"#,
+            mapping.id,
+            at
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|row| Self::to_snapshot(&mapping, row.high, row.low, row.timestamp)))
+    }
+
+    /// The latest stored snapshot taken at or before `at` (UTC), used for price trends
+    pub async fn get_snapshot_before(&self, name: &str, at: NaiveDateTime) -> Result<Option<PriceSnapshot>> {
+        let Some(mapping) = self.get_item_mapping(name).await else {
+            return Ok(None);
+        };
+
+        let row = sqlx::query!(
+            r#"SELECT high, low, timestamp AS "timestamp!: NaiveDateTime"
+             FROM price_snapshots
+             WHERE item_id = ? AND timestamp <= ?
+             ORDER BY timestamp DESC
+             LIMIT 1"#,
+            mapping.id,
+            at
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|row| Self::to_snapshot(&mapping, row.high, row.low, row.timestamp)))
+    }
+
+    fn to_snapshot(mapping: &ItemMapping, high: Option<i64>, low: Option<i64>, timestamp: NaiveDateTime) -> PriceSnapshot {
+        let price = ItemPrice { high, high_time: None, low, low_time: None };
+        PriceSnapshot {
+            value: price.value(mapping),
+            timestamp,
+        }
     }
 } 
\ No newline at end of file
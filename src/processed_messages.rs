@@ -0,0 +1,170 @@
+use anyhow::Result;
+use sqlx::{Executor, Sqlite, SqlitePool};
+use kittyscape_loot_bot::parsers::ParsedEvent;
+
+/// Claims the message for processing. Returns false if it was already processed, or is being processed
+/// right now, so a replayed or re-run message can't award anything twice. A message that failed without
+/// recording anything can be claimed again
+pub async fn claim(db: &SqlitePool, message_id: &str) -> Result<bool> {
+    Ok(sqlx::query!(
+        "INSERT INTO processed_messages (message_id, status) VALUES (?1, 'processing')
+         ON CONFLICT(message_id) DO UPDATE SET status = 'processing', processed_at = CURRENT_TIMESTAMP
+         WHERE status = 'failed' AND NOT EXISTS (SELECT 1 FROM processed_message_rows WHERE message_id = ?1)",
+        message_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected() > 0)
+}
+
+/// Whether the message has been claimed and isn't waiting to be retried
+pub async fn is_processed(db: &SqlitePool, message_id: &str) -> Result<bool> {
+    Ok(sqlx::query!(
+        "SELECT message_id FROM processed_messages
+         WHERE message_id = ?1
+           AND (status != 'failed' OR EXISTS (SELECT 1 FROM processed_message_rows WHERE message_id = ?1))",
+        message_id
+    )
+    .fetch_optional(db)
+    .await?
+    .is_some())
+}
+
+/// Messages that failed without recording anything, oldest first, to be tried again
+pub async fn retryable(db: &SqlitePool) -> Result<Vec<String>> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT message_id AS "message_id!" FROM processed_messages p
+           WHERE status = 'failed'
+             AND NOT EXISTS (SELECT 1 FROM processed_message_rows r WHERE r.message_id = p.message_id)
+           ORDER BY CAST(message_id AS INTEGER)"#
+    )
+    .fetch_all(db)
+    .await?)
+}
+
+/// Stores what the parsers made of the message
+pub async fn record_parse(db: &SqlitePool, message_id: &str, parser: &str, events: &[ParsedEvent]) -> Result<()> {
+    let events = serde_json::to_string(events)?;
+    sqlx::query!(
+        "UPDATE processed_messages SET parser = ?, events = ? WHERE message_id = ?",
+        parser,
+        events,
+        message_id
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Remembers that the message produced this row
+pub async fn link_row<'e, E>(executor: E, message_id: &str, source_table: &'static str, source_id: i64) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query!(
+        "INSERT OR IGNORE INTO processed_message_rows (message_id, source_table, source_id) VALUES (?, ?, ?)",
+        message_id,
+        source_table,
+        source_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Marks the message done or failed. A failed message is tried again on the next startup or backfill,
+/// unless it already recorded something, then it's left for a mod to look at rather than risk awarding it twice
+pub async fn finish<'e, E>(executor: E, message_id: &str, succeeded: bool) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let status = if succeeded {"done"} else {"failed"};
+    sqlx::query!("UPDATE processed_messages SET status = ? WHERE message_id = ?", status, message_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Fails the claims a previous run left in processing. The bot stopped before their transactions committed,
+/// so they recorded nothing and are retried like any other failed message. Call before anything is tracked
+pub async fn expire_claims(db: &SqlitePool) -> Result<u64> {
+    Ok(sqlx::query!("UPDATE processed_messages SET status = 'failed' WHERE status = 'processing'")
+        .execute(db)
+        .await?
+        .rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn database() -> SqlitePool {
+        // Every connection to :memory: is its own database, so the pool keeps just the one
+        let db = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory database");
+        sqlx::migrate!().run(&db).await.expect("migrations");
+        db
+    }
+
+    #[tokio::test]
+    async fn a_message_is_only_claimed_once() {
+        let db = database().await;
+
+        assert!(claim(&db, "1").await.unwrap());
+        assert!(!claim(&db, "1").await.unwrap());
+        assert!(is_processed(&db, "1").await.unwrap());
+
+        finish(&db, "1", true).await.unwrap();
+        assert!(!claim(&db, "1").await.unwrap());
+        assert!(is_processed(&db, "1").await.unwrap());
+        assert!(retryable(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_message_that_recorded_nothing_is_retried() {
+        let db = database().await;
+        claim(&db, "2").await.unwrap();
+        claim(&db, "1").await.unwrap();
+        finish(&db, "2", false).await.unwrap();
+        finish(&db, "1", false).await.unwrap();
+
+        assert!(!is_processed(&db, "1").await.unwrap());
+        assert_eq!(retryable(&db).await.unwrap(), vec!["1", "2"]);
+
+        assert!(claim(&db, "1").await.unwrap());
+        assert!(!claim(&db, "1").await.unwrap());
+        assert_eq!(retryable(&db).await.unwrap(), vec!["2"]);
+    }
+
+    #[tokio::test]
+    async fn a_failed_message_that_recorded_something_is_left_alone() {
+        let db = database().await;
+        claim(&db, "1").await.unwrap();
+        link_row(&db, "1", "drops", 7).await.unwrap();
+        finish(&db, "1", false).await.unwrap();
+
+        assert!(is_processed(&db, "1").await.unwrap());
+        assert!(!claim(&db, "1").await.unwrap());
+        assert!(retryable(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn claims_left_processing_expire_into_retries() {
+        let db = database().await;
+        claim(&db, "1").await.unwrap();
+        claim(&db, "2").await.unwrap();
+        finish(&db, "2", true).await.unwrap();
+
+        assert_eq!(expire_claims(&db).await.unwrap(), 1);
+        assert_eq!(retryable(&db).await.unwrap(), vec!["1"]);
+        assert!(claim(&db, "1").await.unwrap());
+
+        // Nothing is left processing after the first run
+        finish(&db, "1", true).await.unwrap();
+        assert_eq!(expire_claims(&db).await.unwrap(), 0);
+    }
+}
@@ -3,11 +3,13 @@ use serenity::prelude::*;
 use serenity::model::prelude::*;
 use serenity::utils::MessageBuilder;
 use sqlx::SqlitePool;
+use std::collections::HashSet;
 use crate::config::ConfigKey;
 use crate::command_handler::format_points;
+use crate::logger;
+use crate::ledger::{self, PointChange};
 
 pub struct PointsUpdate {
-    pub old_points: i64,
     pub new_points: i64,
     pub next_rank: Option<(i64, String)>,
     pub crossed_ranks: Vec<String>,
@@ -17,9 +19,11 @@ pub async fn add_points(
     ctx: &Context,
     discord_id: &str,
     user_name: &str,
-    points_to_add: i64,
+    change: PointChange<'_>,
     db: &SqlitePool,
 ) -> Result<PointsUpdate> {
+    let points_to_add = change.delta;
+
     // Insert or update user
     sqlx::query!(
         "INSERT INTO users (discord_id, points, total_drops) 
@@ -40,15 +44,7 @@ pub async fn add_points(
     .points;
 
     // Update user points
-    sqlx::query!(
-        "UPDATE users 
-         SET points = points + ?
-         WHERE discord_id = ?",
-        points_to_add,
-        discord_id
-    )
-    .execute(db)
-    .await?;
+    ledger::record(db, discord_id, &change).await?;
 
     // Get new points total
     let new_points = old_points + points_to_add;
@@ -75,6 +71,8 @@ pub async fn add_points(
             
         // Send notification if ranks were crossed
         if !crossed_ranks.is_empty() {
+            // Hand out the matching Discord roles first, so the notification says what actually happened
+            let roles_held = update_rank_roles(ctx, discord_id, user_name, new_points, db).await;
             let data = ctx.data.read().await;
             if let Some(config) = data.get::<ConfigKey>() {
                 // Format ranks list
@@ -94,16 +92,18 @@ pub async fn add_points(
                 // Create notification with all crossed ranks
                 let notification = if ranks_crossed.len() == 1 {
                     format!(
-                        "🎉 **Rank Up Alert!**\n{} has reached {} and is ready for the {} role!",
+                        "🎉 **Rank Up Alert!**\n{} has reached {} and {} the {} role!",
                         user_name,
                         format_points(new_points),
+                        role_verb(&crossed_ranks, &roles_held),
                         ranks_text
                     )
                 } else {
                     format!(
-                        "🎉 **Multiple Rank Up Alert!**\n{} has reached {} and is ready for the following roles: {}!",
+                        "🎉 **Multiple Rank Up Alert!**\n{} has reached {} and {} the following roles: {}!",
                         user_name,
                         format_points(new_points),
+                        role_verb(&crossed_ranks, &roles_held),
                         ranks_text
                     )
                 };
@@ -153,6 +153,7 @@ pub async fn add_points(
             
         // Send notification if ranks were lost
         if !crossed_ranks.is_empty() {
+            update_rank_roles(ctx, discord_id, user_name, new_points, db).await;
             let data = ctx.data.read().await;
             if let Some(config) = data.get::<ConfigKey>() {
                 // Format ranks list
@@ -209,7 +210,6 @@ pub async fn add_points(
     .map(|rank| (rank.points, rank.role_name));
 
     Ok(PointsUpdate {
-        old_points,
         new_points,
         next_rank,
         crossed_ranks,
@@ -236,6 +236,8 @@ pub async fn notify_rank_transition(
         .await?;
 
         if !ranks_crossed.is_empty() {
+            let crossed_names: Vec<String> = ranks_crossed.iter().map(|r| r.1.clone()).collect();
+            let roles_held = update_rank_roles(ctx, discord_id, user_name, new_points, db).await;
             let data = ctx.data.read().await;
             if let Some(config) = data.get::<ConfigKey>() {
                 let ranks_text = if ranks_crossed.len() == 1 {
@@ -253,16 +255,18 @@ pub async fn notify_rank_transition(
 
                 let notification = if ranks_crossed.len() == 1 {
                     format!(
-                        "🎉 **Rank Up Alert!**\n{} has reached {} and is ready for the {} role!",
+                        "🎉 **Rank Up Alert!**\n{} has reached {} and {} the {} role!",
                         user_name,
                         format_points(new_points),
+                        role_verb(&crossed_names, &roles_held),
                         ranks_text
                     )
                 } else {
                     format!(
-                        "🎉 **Multiple Rank Up Alert!**\n{} has reached {} and is ready for the following roles: {}!",
+                        "🎉 **Multiple Rank Up Alert!**\n{} has reached {} and {} the following roles: {}!",
                         user_name,
                         format_points(new_points),
+                        role_verb(&crossed_names, &roles_held),
                         ranks_text
                     )
                 };
@@ -299,6 +303,7 @@ pub async fn notify_rank_transition(
         .await?;
 
         if !ranks_lost.is_empty() {
+            update_rank_roles(ctx, discord_id, user_name, new_points, db).await;
             let data = ctx.data.read().await;
             if let Some(config) = data.get::<ConfigKey>() {
                 let ranks_text = if ranks_lost.len() == 1 {
@@ -338,4 +343,203 @@ pub async fn notify_rank_transition(
     }
 
     Ok(())
+}
+
+/// For point changes written with `ledger::record` inside a transaction, call once it's committed: sends the
+/// rank notifications and syncs roles like `add_points` does, and returns the same summary.
+/// The total before the change is worked out from the member's current points
+pub async fn points_recorded(
+    ctx: &Context,
+    discord_id: &str,
+    user_name: &str,
+    delta: i64,
+    db: &SqlitePool,
+) -> Result<PointsUpdate> {
+    let new_points = sqlx::query!("SELECT points FROM users WHERE discord_id = ?", discord_id)
+        .fetch_one(db)
+        .await?
+        .points;
+    let old_points = new_points - delta;
+
+    notify_rank_transition(ctx, discord_id, user_name, old_points, new_points, db).await?;
+
+    let (lower, upper) = (old_points.min(new_points), old_points.max(new_points));
+    let mut crossed_ranks = sqlx::query_scalar!(
+        "SELECT role_name FROM rank_thresholds
+         WHERE points > ? AND points <= ?
+         ORDER BY points ASC",
+        lower,
+        upper
+    )
+    .fetch_all(db)
+    .await?;
+    if delta < 0 {
+        crossed_ranks.reverse();
+    }
+
+    let next_rank = sqlx::query!(
+        "SELECT points, role_name FROM rank_thresholds 
+         WHERE points > ? 
+         ORDER BY points ASC 
+         LIMIT 1",
+        new_points
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|rank| (rank.points, rank.role_name));
+
+    Ok(PointsUpdate {
+        new_points,
+        next_rank,
+        crossed_ranks,
+    })
+}
+
+/// "has been given" only when the member really holds every crossed rank's role now
+fn role_verb(crossed_ranks: &[String], roles_held: &[String]) -> &'static str {
+    if crossed_ranks.iter().all(|rank| roles_held.contains(rank)) { "has been given" } else { "is ready for" }
+}
+
+/// Result of reconciling one member's rank roles
+#[derive(Default)]
+pub struct RoleSync {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Roles Discord refused to add or remove, with why, e.g. a role above the bot's own
+    pub failed: Vec<(String, String)>,
+}
+
+/// A rank threshold matched up with the guild role of the same name
+pub struct RankRole {
+    pub points: i64,
+    pub role_name: String,
+    pub role_id: RoleId,
+}
+
+/// Matches every `rank_thresholds.role_name` to a guild role by name (case-insensitive).
+/// Ranks without a matching guild role are skipped with a warning.
+pub async fn load_rank_roles(ctx: &Context, guild_id: GuildId, db: &SqlitePool) -> Result<Vec<RankRole>> {
+    let thresholds = sqlx::query!(
+        "SELECT points, role_name FROM rank_thresholds ORDER BY points ASC"
+    )
+    .fetch_all(db)
+    .await?;
+
+    // The guild's roles are cached from the gateway, only fetch them if the guild isn't cached yet
+    let cached_roles = ctx.cache.guild(guild_id).map(|guild| guild.roles.clone());
+    let guild_roles = match cached_roles {
+        Some(roles) => roles,
+        None => guild_id.roles(&ctx.http).await?,
+    };
+
+    let mut rank_roles = Vec::new();
+    for threshold in thresholds {
+        match guild_roles.values().find(|role| role.name.eq_ignore_ascii_case(&threshold.role_name)) {
+            Some(role) => rank_roles.push(RankRole {
+                points: threshold.points,
+                role_name: threshold.role_name,
+                role_id: role.id,
+            }),
+            None => tracing::warn!("No guild role found for rank '{}'", threshold.role_name),
+        }
+    }
+
+    Ok(rank_roles)
+}
+
+/// Gives a member every rank role at or below `points` and takes away every rank role above it.
+/// A role that can't be changed is noted in the result and the rest are still synced.
+/// Returns `None` if the user is not a member of the guild.
+pub async fn sync_member_roles(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    points: i64,
+    rank_roles: &[RankRole],
+) -> Result<Option<RoleSync>> {
+    let member = match guild_id.member(ctx, user_id).await {
+        Ok(member) => member,
+        Err(why) => {
+            tracing::debug!("Could not fetch member {} for role sync: {:?}", user_id, why);
+            return Ok(None);
+        }
+    };
+    let current: HashSet<RoleId> = member.roles.iter().copied().collect();
+
+    let mut sync = RoleSync::default();
+    for rank in rank_roles {
+        let should_have = rank.points <= points;
+        let has = current.contains(&rank.role_id);
+
+        if should_have && !has {
+            match ctx.http
+                .add_member_role(guild_id, user_id, rank.role_id, Some("Reached rank points threshold"))
+                .await
+            {
+                Ok(()) => sync.added.push(rank.role_name.clone()),
+                Err(why) => sync.failed.push((rank.role_name.clone(), why.to_string())),
+            }
+        } else if !should_have && has {
+            match ctx.http
+                .remove_member_role(guild_id, user_id, rank.role_id, Some("Dropped below rank points threshold"))
+                .await
+            {
+                Ok(()) => sync.removed.push(rank.role_name.clone()),
+                Err(why) => sync.failed.push((rank.role_name.clone(), why.to_string())),
+            }
+        }
+    }
+
+    Ok(Some(sync))
+}
+
+/// Syncs a single user's rank roles after a points change and returns the rank roles they hold afterwards.
+/// Returns nothing if `GUILD_ID` isn't set, the user isn't in the guild or the sync failed.
+/// Failures are reported to the log channel rather than returned, so they never undo the points change.
+async fn update_rank_roles(ctx: &Context, discord_id: &str, user_name: &str, points: i64, db: &SqlitePool) -> Vec<String> {
+    let guild_id = {
+        let data = ctx.data.read().await;
+        match data.get::<ConfigKey>().and_then(|config| config.guild_id) {
+            Some(guild_id) => guild_id,
+            None => return Vec::new(),
+        }
+    };
+
+    let user_id = match discord_id.parse::<u64>() {
+        Ok(id) => UserId::new(id),
+        Err(_) => return Vec::new(),
+    };
+
+    let result = async {
+        let rank_roles = load_rank_roles(ctx, guild_id, db).await?;
+        let sync = sync_member_roles(ctx, guild_id, user_id, points, &rank_roles).await?;
+        anyhow::Ok((rank_roles, sync))
+    }
+    .await;
+
+    match result {
+        Ok((rank_roles, Some(sync))) => {
+            for (role_name, why) in &sync.failed {
+                tracing::error!("Failed to update rank role {} for {}: {}", role_name, discord_id, why);
+                let _ = logger::log_generic(
+                    ctx,
+                    &format!("⚠️ Failed to update rank role {} for {} ({}): {}", role_name, user_name, discord_id, why)
+                ).await;
+            }
+            rank_roles
+                .into_iter()
+                .filter(|rank| rank.points <= points && !sync.failed.iter().any(|(role_name, _)| *role_name == rank.role_name))
+                .map(|rank| rank.role_name)
+                .collect()
+        }
+        Ok((_, None)) => Vec::new(),
+        Err(why) => {
+            tracing::error!("Failed to update rank roles for {}: {:?}", discord_id, why);
+            let _ = logger::log_generic(
+                ctx,
+                &format!("⚠️ Failed to update rank roles for {} ({}): {}", user_name, discord_id, why)
+            ).await;
+            Vec::new()
+        }
+    }
 }
\ No newline at end of file
@@ -1,177 +1,346 @@
 use anyhow::Result;
-use regex::Regex;
-use serenity::all::Message;
+use chrono::NaiveDateTime;
+use serenity::all::{ChannelId, EditMessage, GetMessages, Message, MessageId, Reaction};
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use std::sync::Arc;
-use lazy_static::lazy_static;
-use tracing::{info, warn, error, debug};
+use tracing::{warn, error, debug, info};
+use kittyscape_loot_bot::parsers::{ClogEvent, DropEvent, ParsedEvent, ParserRegistry, PetEvent};
+use crate::command_handler::pet::{self, NewPet};
 use crate::rank_manager;
+use crate::scoring::ScoringRules;
+use crate::ledger::{self, PointChange, Reason};
+use crate::screenshots::{self, Screenshot};
+use crate::splits;
+use crate::unlinked;
+use crate::processed_messages;
 
-lazy_static! {
-    // Regular expressions for parsing messages from RuneLite plugins
-    static ref DROP_REGEX: Regex = Regex::new(r"^(.+) received: (.+)(?: \((\d+)x\))? \(([0-9,]+) coins\)$").unwrap();
-    static ref CLOG_REGEX: Regex = Regex::new(r"(?:\*\*(.+)\*\*\s+New item added to your collection log: \*\*(.+)\*\*|^(.+) received a collection log item: (.+)$)").unwrap();
-    
-    // Regex for parsing embed drop notifications
-    // This pattern handles formats like:
-    // "Just got [Coal] from [Monster]"
-    // "Just got 5x [Coal] from [Monster]"
-    // "Just got [Coal](url) from [Monster](url)"
-    // "Just got 5x [Coal](url) from lvl 98 [Monster](url)"
-    static ref EMBED_DROP_REGEX: Regex = Regex::new(r"Just got (?:(\d+)x\s+)?\[(.+?)(?:\]\(.+?\)|\])\s+from(?:\s+lvl\s+\d+)?\s+\[(.+?)(?:\]\(.+?\)|\])").unwrap();
-    static ref EMBED_VALUE_REGEX: Regex = Regex::new(r"```fix\s*([0-9,]+) GP\s*```").unwrap();
-}
+/// Discord's epoch, the start of 2015, in milliseconds. Message IDs count from it
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+/// Most messages Discord returns per history request
+const HISTORY_PAGE_SIZE: u8 = 100;
 
 pub struct RunescapeTracker {
-    // Add fields as needed
+    parsers: ParserRegistry,
+}
+
+/// Someone an RS name is linked to
+struct Owner {
+    discord_id: String,
+    user_name: String,
+}
+
+/// What every event in the message being tracked shares, all looked up before its transaction starts
+struct Tracking<'a> {
+    msg: &'a Message,
+    message_id: String,
+    /// When the message was posted, which is what its rows are dated, even when it's backfilled later
+    timestamp: NaiveDateTime,
+    screenshot: Screenshot,
+    rules: ScoringRules,
+    /// The accounts each RS name in the message is linked to
+    owners: HashMap<String, Vec<Owner>>,
+}
+
+impl Tracking<'_> {
+    fn owners(&self, rs_name: &str) -> &[Owner] {
+        self.owners.get(rs_name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Points a message awarded, announced once its transaction is committed
+struct Award {
+    discord_id: String,
+    user_name: String,
+    points: i64,
+    /// The bot log's action and details
+    action: &'static str,
+    details: String,
+}
+
+/// What a message recorded
+#[derive(Default)]
+struct Recorded {
+    awards: Vec<Award>,
+    /// Whether it recorded anything for a linked member, to react with a checkmark
+    checked: bool,
+    /// Whether that included drops teammates can join
+    joinable: bool,
+}
+
+/// What a /backfill or startup catch-up went through
+#[derive(Debug, Default)]
+pub struct BackfillSummary {
+    pub processed: usize,
+    /// Messages that had already been processed
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl BackfillSummary {
+    /// e.g. "12 messages processed, 3 were already processed, 1 failed"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} messages processed, {} were already processed, {} failed",
+            self.processed, self.skipped, self.failed
+        )
+    }
 }
 
 impl RunescapeTracker {
     pub async fn new() -> Result<Self> {
-        Ok(Self {})
+        Ok(Self {
+            parsers: ParserRegistry::default(),
+        })
     }
 
+    /// Tracks everything in the message, at most once per message however often it's seen. Everything it
+    /// records, and marking it done, is written in one transaction, so a failure or crash part-way leaves
+    /// nothing behind and the message can simply be tried again. Notifications go out once it's committed
     pub async fn process_message(&self, ctx: &Context, msg: &Message, db: &SqlitePool) -> Result<()> {
-        let content = &msg.content;
-        debug!("Processing message in RuneLite channel: ID={}, Author={}, Content={}", msg.id, msg.author.name, content);
+        let message_id = msg.id.to_string();
+        if !processed_messages::claim(db, &message_id).await? {
+            debug!("Message {} was already processed, skipping it", msg.id);
+            return Ok(());
+        }
+
+        let recorded = match self.track_message(ctx, msg, db).await {
+            Ok(recorded) => recorded,
+            Err(why) => {
+                processed_messages::finish(db, &message_id, false).await?;
+                return Err(why);
+            }
+        };
+        self.announce(ctx, msg, db, recorded).await
+    }
+
+    async fn track_message(&self, ctx: &Context, msg: &Message, db: &SqlitePool) -> Result<Recorded> {
+        debug!("Processing message in RuneLite channel: ID={}, Author={}, Content={}", msg.id, msg.author.name, msg.content);
+        let message_id = msg.id.to_string();
+        let mut recorded = Recorded::default();
         
-        // Check if the message has embeds
-        if !msg.embeds.is_empty() {
-            debug!("Message has {} embeds", msg.embeds.len());
-            
-            // Process each embed
-            for (i, embed) in msg.embeds.iter().enumerate() {
-                debug!("Processing embed #{}", i + 1);
-                
-                // Check for embed description
-                if let Some(description) = &embed.description {
-                    debug!("Embed description: {}", description);
-                    
-                    // Try to extract item name and source from the description
-                    if let Some(captures) = EMBED_DROP_REGEX.captures(description) {
-                        let item_name = captures.get(2).map_or("", |m| m.as_str()).trim();
-                        let source = captures.get(3).map_or("", |m| m.as_str()).trim();
-                        debug!("Found item in embed: {}", item_name);
-                        debug!("From source: {}", source);
-                        
-                        // Parse quantity - default to 1 if not present
-                        let quantity: i64 = captures.get(1).map_or("1", |m| m.as_str()).parse().unwrap_or(1);
-                        debug!("Quantity: {}", quantity);
-                        
-                        // Try to extract the value from the GE Value field
-                        let mut value: i64 = 0;
-                        for field in &embed.fields {
-                            if field.name == "GE Value" && !field.value.is_empty() {
-                                debug!("Found GE Value field: {}", field.value);
-                                if let Some(value_capture) = EMBED_VALUE_REGEX.captures(&field.value) {
-                                    let value_str = value_capture.get(1).map_or("0", |m| m.as_str()).replace(",", "");
-                                    value = value_str.parse().unwrap_or(0);
-                                    debug!("Parsed value: {}", value);
-                                }
-                            }
-                        }
-                        
-                        // Try to get the RS name from the embed author if it exists
-                        let mut rs_name = String::new();
-                        if let Some(author) = &embed.author {
-                            debug!("Found embed author: {}", author.name);
-                            if !author.name.is_empty() {
-                                rs_name = author.name.clone();
-                                debug!("Using RS name from embed author: {}", rs_name);
-                            }
-                        } else {
-                            debug!("No author field found in embed");
-                        }
-                        
-                        // If no RS name found in author, we can't process this embed
-                        // since the message author will just be the webhook bot
-                        if rs_name.is_empty() {
-                            debug!("No RS name found in embed author for item: {}", item_name);
-                            return Ok(());
-                        }
-                        
-                        debug!("Processing drop from embed: {} received {}x {} worth {} from {}", 
-                               rs_name, quantity, item_name, value, source);
-                        // Process the drop notification from the embed
-                        self.process_drop(ctx, &rs_name, item_name, quantity, value, db, msg).await?;
-                        return Ok(());
-                    } else {
-                        debug!("Could not find drop pattern in embed description");
-                    }
-                } else {
-                    debug!("Embed has no description");
+        let Some((parser, events)) = self.parsers.parse(msg).await else {
+            debug!("Could not parse message format: {}", msg.content);
+            processed_messages::finish(db, &message_id, true).await?;
+            return Ok(recorded);
+        };
+        
+        debug!("Message {} parsed by {} parser into {} events", msg.id, parser, events.len());
+        processed_messages::record_parse(db, &message_id, parser, &events).await?;
+
+        // One screenshot covers every drop, clog and pet in the message, so it's only saved once
+        let is_loot = |event: &ParsedEvent| matches!(event, ParsedEvent::Drop(_) | ParsedEvent::Clog(_) | ParsedEvent::Pet(_));
+        let has_loot = events.iter().any(is_loot);
+        let screenshot = match screenshots::message_attachment(&msg.attachments) {
+            Some(attachment) if has_loot => screenshots::archive(ctx, attachment).await,
+            _ => Screenshot::default(),
+        };
+
+        // Who each RS name belongs to is looked up first, so nothing waits on Discord inside the transaction
+        let mut owners = HashMap::new();
+        for event in events.iter().filter(|event| is_loot(event)) {
+            if !owners.contains_key(event.rs_name()) {
+                owners.insert(event.rs_name().to_string(), self.owners(ctx, event.rs_name(), db).await?);
+            }
+        }
+
+        let tracking = Tracking {
+            msg,
+            message_id,
+            timestamp: msg.timestamp.naive_utc(),
+            screenshot,
+            rules: ScoringRules::load(db).await?,
+            owners,
+        };
+
+        let mut tx = db.begin().await?;
+        for event in &events {
+            let awards = match event {
+                ParsedEvent::Drop(drop) => self.process_drop(ctx, drop, &mut tx, &tracking).await?,
+                ParsedEvent::Clog(clog) => self.process_clog(ctx, clog, &mut tx, &tracking).await?,
+                ParsedEvent::Pet(pet) => self.process_pet(ctx, pet, &mut tx, &tracking).await?,
+                ParsedEvent::Level(level) => {
+                    debug!("Level up for {}: {:?}, levels are not tracked", level.rs_name, level.skills);
+                    None
                 }
+                ParsedEvent::Ignored { rs_name, kind } => {
+                    debug!("Ignoring {} notification for {}", kind, rs_name);
+                    None
+                }
+            };
+            if let Some(awards) = awards {
+                recorded.checked = true;
+                recorded.joinable |= matches!(event, ParsedEvent::Drop(_));
+                recorded.awards.extend(awards);
             }
         }
+        processed_messages::finish(&mut *tx, &tracking.message_id, true).await?;
+        tx.commit().await?;
         
-        // Try to parse drop message from plain text
-        if let Some(captures) = DROP_REGEX.captures(content) {
-            let rs_name = captures.get(1).map_or("", |m| m.as_str()).trim();
-            let item_name = captures.get(2).map_or("", |m| m.as_str()).trim();
-            let quantity: i64 = captures.get(3)
-                .map_or("1", |m| m.as_str())
-                .parse()
-                .unwrap_or(1);
-            let value_str = captures.get(4).map_or("0", |m| m.as_str()).replace(",", "");
-            let value: i64 = value_str.parse().unwrap_or(0);
-            
-            debug!("Parsed drop from text: {} received {}x {} worth {}", rs_name, quantity, item_name, value);
-            // Process the drop notification
-            self.process_drop(ctx, rs_name, item_name, quantity, value, db, msg).await?;
-            return Ok(());
+        Ok(recorded)
+    }
+
+    /// Sends the rank notifications and bot log lines for what the message recorded and reacts to it
+    async fn announce(&self, ctx: &Context, msg: &Message, db: &SqlitePool, recorded: Recorded) -> Result<()> {
+        for award in recorded.awards {
+            debug!("Adding {} points to {} ({})", award.points, award.user_name, award.discord_id);
+            rank_manager::points_recorded(ctx, &award.discord_id, &award.user_name, award.points, db).await?;
+            crate::logger::log_action(ctx, &award.discord_id, award.action, &award.details).await?;
         }
-        
-        // Try to parse collection log message
-        if let Some(captures) = CLOG_REGEX.captures(content) {
-            // Try the new format first (with bold/asterisks)
-            let (rs_name, item_name) = if let (Some(name), Some(item)) = (captures.get(1), captures.get(2)) {
-                (name.as_str().trim(), item.as_str().trim())
-            } 
-            // Fall back to the original format
-            else if let (Some(name), Some(item)) = (captures.get(3), captures.get(4)) {
-                (name.as_str().trim(), item.as_str().trim())
-            } else {
-                ("", "")
+
+        // A checkmark for anything recorded, and the reaction teammates can use to join its drops
+        if recorded.checked {
+            let _ = msg.react(ctx, '✅').await;
+        }
+        if recorded.joinable {
+            let _ = msg.react(ctx, splits::join_reaction()).await;
+        }
+
+        Ok(())
+    }
+
+    /// Runs every bot message in the channel posted after `after` through `process_message`, oldest first,
+    /// skipping the ones already processed. Stops at the newest message when it started, the live handler
+    /// takes it from there. `progress` is edited with the counts so far after every page and once at the end
+    pub async fn backfill(
+        &self,
+        ctx: &Context,
+        db: &SqlitePool,
+        channel_id: ChannelId,
+        after: MessageId,
+        mut progress: Option<&mut Message>,
+    ) -> Result<BackfillSummary> {
+        let mut summary = BackfillSummary::default();
+
+        let Some(until) = channel_id.messages(&ctx.http, GetMessages::new().limit(1)).await?.first().map(|msg| msg.id) else {
+            return Ok(summary);
+        };
+
+        let mut cursor = after;
+        while cursor < until {
+            let mut page = channel_id
+                .messages(&ctx.http, GetMessages::new().after(cursor).limit(HISTORY_PAGE_SIZE))
+                .await?;
+            if page.is_empty() {
+                break;
+            }
+            page.sort_by_key(|msg| msg.id);
+            cursor = page.last().map_or(until, |msg| msg.id);
+
+            for msg in page.iter().filter(|msg| msg.id <= until && is_runelite_message(ctx, msg)) {
+                if processed_messages::is_processed(db, &msg.id.to_string()).await? {
+                    summary.skipped += 1;
+                    continue;
+                }
+                match self.process_message(ctx, msg, db).await {
+                    Ok(()) => summary.processed += 1,
+                    Err(why) => {
+                        error!("Error backfilling RuneLite message {}: {:?}", msg.id, why);
+                        summary.failed += 1;
+                    }
+                }
+            }
+
+            if page.len() < HISTORY_PAGE_SIZE as usize {
+                break;
+            }
+            report_progress(ctx, progress.as_deref_mut(), &summary).await;
+        }
+
+        // The last page is usually a short one, which ends the loop before it's reported
+        report_progress(ctx, progress, &summary).await;
+        Ok(summary)
+    }
+
+    /// Runs the messages that failed without recording anything through `process_message` again
+    pub async fn retry_failed(&self, ctx: &Context, db: &SqlitePool, channel_id: ChannelId) -> Result<BackfillSummary> {
+        let mut summary = BackfillSummary::default();
+
+        for message_id in processed_messages::retryable(db).await? {
+            let Ok(id) = message_id.parse::<u64>() else {
+                continue;
             };
-            
-            if !rs_name.is_empty() && !item_name.is_empty() {
-                debug!("Parsed collection log from text: {} received {}", rs_name, item_name);
-                // Process the collection log notification
-                self.process_clog(ctx, rs_name, item_name, db, msg).await?;
-                return Ok(());
+            let msg = match channel_id.message(&ctx.http, MessageId::new(id)).await {
+                Ok(msg) => msg,
+                Err(why) => {
+                    warn!("Could not fetch failed RuneLite message {} to retry it: {:?}", message_id, why);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            // One of the bot's own replies, claimed before they were left out. There's nothing in it to retry
+            if !is_runelite_message(ctx, &msg) {
+                processed_messages::finish(db, &message_id, true).await?;
+                continue;
+            }
+            match self.process_message(ctx, &msg, db).await {
+                Ok(()) => summary.processed += 1,
+                Err(why) => {
+                    error!("Error retrying RuneLite message {}: {:?}", msg.id, why);
+                    summary.failed += 1;
+                }
             }
         }
-        
-        // If we get here, we couldn't parse the message
-        debug!("Could not parse message format: {}", content);
+
+        Ok(summary)
+    }
+
+    /// Retries the messages that failed, then backfills from the newest processed message, picking up
+    /// whatever was posted while the bot was offline
+    pub async fn catch_up(&self, ctx: &Context, db: &SqlitePool, channel_id: ChannelId) -> Result<()> {
+        let retried = self.retry_failed(ctx, db, channel_id).await?;
+        if retried.processed + retried.failed > 0 {
+            info!("Retried failed RuneLite messages: {} processed, {} failed again", retried.processed, retried.failed);
+        }
+
+        let last_processed = sqlx::query_scalar!(
+            r#"SELECT MAX(CAST(message_id AS INTEGER)) AS "message_id: i64" FROM processed_messages"#
+        )
+        .fetch_one(db)
+        .await?;
+
+        let Some(last_processed) = last_processed.and_then(|id| u64::try_from(id).ok()).filter(|id| *id > 0) else {
+            info!("No RuneLite messages processed yet, use /backfill to go through the channel's history");
+            return Ok(());
+        };
+
+        let summary = self.backfill(ctx, db, channel_id, MessageId::new(last_processed), None).await?;
+        info!("RuneLite catch-up done: {}", summary.describe());
         Ok(())
     }
     
+    /// Writes the drop for everyone the RS name is linked to. Returns `None` if it's linked to nobody
     async fn process_drop(
         &self,
         ctx: &Context,
-        rs_name: &str,
-        item_name: &str,
-        quantity: i64,
-        value: i64,
-        db: &SqlitePool,
-        original_msg: &Message
-    ) -> Result<()> {
+        drop: &DropEvent,
+        conn: &mut SqliteConnection,
+        tracking: &Tracking<'_>
+    ) -> Result<Option<Vec<Award>>> {
+        let rs_name = drop.rs_name.as_str();
+        let item_name = drop.item_name.as_str();
+        let item_id = drop.item_id;
+        let quantity = drop.quantity;
+        let value = drop.value;
+        let source = drop.source.as_deref();
         debug!("Processing drop for {} - Item: {}, Quantity: {}, Value: {}", rs_name, item_name, quantity, value);
 
-        // Look up the Discord ID for this Runescape username
-        let discord_ids = self.get_discord_ids_for_rs_name(rs_name, db).await?;
-        
-        if discord_ids.is_empty() {
+        let owners = tracking.owners(rs_name);
+        if owners.is_empty() {
             debug!("No Discord account linked to RS name '{}' for drop: {}", rs_name, item_name);
-            return Ok(());
+            unlinked::store(ctx, conn, &ParsedEvent::Drop(drop.clone()), &tracking.screenshot, tracking.msg).await?;
+            return Ok(None);
         }
 
-        debug!("Found {} Discord accounts linked to RS name '{}'", discord_ids.len(), rs_name);
+        debug!("Found {} Discord accounts linked to RS name '{}'", owners.len(), rs_name);
+
+        let points = tracking.rules.drop_points(item_name, quantity, value);
+        let mut awards = Vec::new();
         
         // Process drop for each linked Discord account
-        for discord_id in discord_ids {
+        for owner in owners {
+            let discord_id = owner.discord_id.as_str();
             debug!("Processing drop for Discord ID: {}", discord_id);
             
             // Insert or update user
@@ -181,19 +350,29 @@ impl RunescapeTracker {
                  ON CONFLICT(discord_id) DO NOTHING",
                 discord_id
             )
-            .execute(db)
+            .execute(&mut *conn)
             .await?;
 
             // Record the drop
-            sqlx::query!(
-                "INSERT INTO drops (discord_id, item_name, value, quantity) VALUES (?, ?, ?, ?)",
+            let drop_id = sqlx::query!(
+                "INSERT INTO drops (discord_id, item_name, value, quantity, item_id, source, points, timestamp, screenshot_url, screenshot_path, message_id)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 discord_id,
                 item_name,
                 value,
-                quantity
+                quantity,
+                item_id,
+                source,
+                points,
+                tracking.timestamp,
+                tracking.screenshot.url,
+                tracking.screenshot.path,
+                tracking.message_id
             )
-            .execute(db)
-            .await?;
+            .execute(&mut *conn)
+            .await?
+            .last_insert_rowid();
+            processed_messages::link_row(&mut *conn, &tracking.message_id, "drops", drop_id).await?;
 
             // Update total drops
             sqlx::query!(
@@ -203,64 +382,65 @@ impl RunescapeTracker {
                 quantity,
                 discord_id
             )
-            .execute(db)
+            .execute(&mut *conn)
             .await?;
 
-            // Calculate points (1 point per 100K gp)
-            let points = value / 100_000;
-            
-            // Get user name for rank updates
-            let user_name = match self.get_username_from_discord_id(ctx, &discord_id).await {
-                Ok(name) => name,
-                Err(_) => format!("Unknown ({})", discord_id),
-            };
-
-            // Add points and check for rank up
-            debug!("Adding {} points to {} ({})", points, user_name, discord_id);
-            rank_manager::add_points(
-                ctx,
-                &discord_id,
-                &user_name,
-                points,
-                db
-            ).await?;
+            ledger::record(&mut *conn, discord_id, &PointChange::new(points, Reason::Drop).source("drops", drop_id)).await?;
             
             debug!("Auto-added drop for {}: {}x {} worth {} GP (Discord ID: {})", 
                   rs_name, quantity, item_name, value, discord_id);
-                  
-            // Log the auto-added drop to the bot log channel
-            crate::logger::log_action(
-                ctx,
-                &discord_id,
-                "AUTO-DROP",
-                &format!("{} received {}x {} worth {} GP", rs_name, quantity, item_name, value)
-            ).await?;
+
+            awards.push(Award {
+                discord_id: owner.discord_id.clone(),
+                user_name: owner.user_name.clone(),
+                points,
+                action: "AUTO-DROP",
+                details: format!("{} received {}x {} worth {} GP{}", rs_name, quantity, item_name, value, tracking.screenshot.link()),
+            });
         }
         
-        // Add a checkmark reaction to the original message
-        let _ = original_msg.react(ctx, '✅').await;
-        
+        Ok(Some(awards))
+    }
+
+    /// A teammate reacting to a RuneLite message to ask for a share of its drops
+    pub async fn process_reaction(&self, ctx: &Context, reaction: &Reaction, db: &SqlitePool) -> Result<()> {
+        if !reaction.emoji.unicode_eq(splits::JOIN_EMOJI) {
+            return Ok(());
+        }
+        let Some(user_id) = reaction.user_id else {
+            return Ok(());
+        };
+        // The bot's own hint reaction
+        if user_id == ctx.cache.current_user().id {
+            return Ok(());
+        }
+
+        splits::request_join(ctx, db, reaction.channel_id, &reaction.message_id.to_string(), &user_id.to_string()).await?;
+
         Ok(())
     }
     
+    /// Writes the collection log entry for everyone the RS name is linked to who doesn't have it yet.
+    /// Returns `None` if it's linked to nobody or the item isn't in the collection log
     async fn process_clog(
         &self,
         ctx: &Context,
-        rs_name: &str,
-        item_name: &str,
-        db: &SqlitePool,
-        original_msg: &Message
-    ) -> Result<()> {
+        clog: &ClogEvent,
+        conn: &mut SqliteConnection,
+        tracking: &Tracking<'_>
+    ) -> Result<Option<Vec<Award>>> {
+        let rs_name = clog.rs_name.as_str();
+        let item_name = clog.item_name.as_str();
         debug!("Processing collection log entry for {} - Item: {}", rs_name, item_name);
-        // Look up the Discord ID for this Runescape username
-        let discord_ids = self.get_discord_ids_for_rs_name(rs_name, db).await?;
-        
-        if discord_ids.is_empty() {
+
+        let owners = tracking.owners(rs_name);
+        if owners.is_empty() {
             debug!("No Discord account linked to RS name '{}' for clog: {}", rs_name, item_name);
-            return Ok(());
+            unlinked::store(ctx, conn, &ParsedEvent::Clog(clog.clone()), &tracking.screenshot, tracking.msg).await?;
+            return Ok(None);
         }
         
-        debug!("Found {} Discord accounts linked to RS name '{}' for collection log", discord_ids.len(), rs_name);
+        debug!("Found {} Discord accounts linked to RS name '{}' for collection log", owners.len(), rs_name);
         
         // Get collection log manager from context data
         let data = ctx.data.read().await;
@@ -268,7 +448,7 @@ impl RunescapeTracker {
             Some(manager) => manager,
             None => {
                 error!("Collection log manager not found");
-                return Ok(());
+                return Ok(None);
             }
         };
         
@@ -280,12 +460,14 @@ impl RunescapeTracker {
             },
             None => {
                 warn!("Could not calculate points for clog item: {}", item_name);
-                return Ok(());
+                return Ok(None);
             }
         };
+        let mut awards = Vec::new();
         
         // Process clog for each linked Discord account
-        for discord_id in discord_ids {
+        for owner in owners {
+            let discord_id = owner.discord_id.as_str();
             debug!("Processing collection log for Discord ID: {}", discord_id);
             
             // Check if user already has this collection log entry
@@ -295,7 +477,7 @@ impl RunescapeTracker {
                 discord_id,
                 item_name
             )
-            .fetch_optional(db)
+            .fetch_optional(&mut *conn)
             .await
             {
                 debug!("User {} already has collection log entry for {}", discord_id, item_name);
@@ -309,53 +491,109 @@ impl RunescapeTracker {
                 ON CONFLICT(discord_id) DO NOTHING",
                 discord_id
             )
-            .execute(db)
+            .execute(&mut *conn)
             .await?;
 
             // Record the collection log entry
-            sqlx::query!(
-                "INSERT INTO collection_log_entries (discord_id, item_name, points) VALUES (?, ?, ?)",
+            let entry_id = sqlx::query!(
+                "INSERT INTO collection_log_entries (discord_id, item_name, points, timestamp, screenshot_url, screenshot_path) VALUES (?, ?, ?, ?, ?, ?)",
                 discord_id,
                 item_name,
-                points
+                points,
+                tracking.timestamp,
+                tracking.screenshot.url,
+                tracking.screenshot.path
             )
-            .execute(db)
-            .await?;
-            
-            // Get user name for rank updates
-            let user_name = match self.get_username_from_discord_id(ctx, &discord_id).await {
-                Ok(name) => name,
-                Err(_) => format!("Unknown ({})", discord_id),
-            };
+            .execute(&mut *conn)
+            .await?
+            .last_insert_rowid();
+            processed_messages::link_row(&mut *conn, &tracking.message_id, "collection_log_entries", entry_id).await?;
 
-            // Add points and check for rank up
-            debug!("Adding {} points to {} ({}) for collection log item", points, user_name, discord_id);
-            rank_manager::add_points(
-                ctx,
-                &discord_id,
-                &user_name,
-                points,
-                db
-            ).await?;
+            ledger::record(&mut *conn, discord_id, &PointChange::new(points, Reason::Clog).source("collection_log_entries", entry_id)).await?;
             
             debug!("Auto-added collection log entry for {}: {} (+{} points) (Discord ID: {})", 
                   rs_name, item_name, points, discord_id);
-                  
-            // Log the auto-added collection log entry to the bot log channel
-            crate::logger::log_action(
-                ctx,
-                &discord_id,
-                "AUTO-CLOG",
-                &format!("{} received collection log item: {} (+{} points)", rs_name, item_name, points)
-            ).await?;
+
+            awards.push(Award {
+                discord_id: owner.discord_id.clone(),
+                user_name: owner.user_name.clone(),
+                points,
+                action: "AUTO-CLOG",
+                details: format!("{} received collection log item: {} (+{} points){}", rs_name, item_name, points, tracking.screenshot.link()),
+            });
         }
         
-        // Add a checkmark reaction to the original message
-        let _ = original_msg.react(ctx, '✅').await;
-        
-        Ok(())
+        Ok(Some(awards))
     }
     
+    /// Writes the pet for everyone the RS name is linked to. Returns `None` if it's linked to nobody
+    async fn process_pet(
+        &self,
+        ctx: &Context,
+        pet: &PetEvent,
+        conn: &mut SqliteConnection,
+        tracking: &Tracking<'_>
+    ) -> Result<Option<Vec<Award>>> {
+        let rs_name = pet.rs_name.as_str();
+        let pet_name = pet.pet_name.as_deref();
+        debug!("Processing pet for {} - Pet: {:?}, Duplicate: {}", rs_name, pet_name, pet.duplicate);
+
+        let owners = tracking.owners(rs_name);
+        if owners.is_empty() {
+            debug!("No Discord account linked to RS name '{}' for pet: {:?}", rs_name, pet_name);
+            unlinked::store(ctx, conn, &ParsedEvent::Pet(pet.clone()), &tracking.screenshot, tracking.msg).await?;
+            return Ok(None);
+        }
+
+        let mut awards = Vec::new();
+
+        for owner in owners {
+            // A named pet the member already logged some other way is a duplicate, even if the game didn't say so
+            let duplicate = pet.duplicate || match pet_name {
+                Some(pet_name) => pet::pet_logged_at(&mut *conn, &owner.discord_id, pet_name).await.is_some(),
+                None => false,
+            };
+
+            let points = tracking.rules.pet_points(duplicate);
+            let new_pet = NewPet {
+                discord_id: &owner.discord_id,
+                user_name: &owner.user_name,
+                pet_name,
+                duplicate,
+                screenshot: &tracking.screenshot,
+                actor_id: None,
+                message_id: Some(&tracking.message_id),
+                when: Some(tracking.timestamp),
+            };
+            let pet_id = pet::insert_pet(conn, &new_pet, points).await?;
+
+            debug!("Auto-added pet for {}: {:?} (+{} points) (Discord ID: {})", rs_name, pet_name, points, owner.discord_id);
+
+            awards.push(Award {
+                discord_id: owner.discord_id.clone(),
+                user_name: owner.user_name.clone(),
+                points,
+                action: "AUTO-PET",
+                details: pet::log_details(&new_pet, points, pet_id),
+            });
+        }
+
+        Ok(Some(awards))
+    }
+
+    /// The Discord accounts the RS name is linked to, with the names their notifications use
+    async fn owners(&self, ctx: &Context, rs_name: &str, db: &SqlitePool) -> Result<Vec<Owner>> {
+        let mut owners = Vec::new();
+        for discord_id in self.get_discord_ids_for_rs_name(rs_name, db).await? {
+            let user_name = match self.get_username_from_discord_id(ctx, &discord_id).await {
+                Ok(name) => name,
+                Err(_) => format!("Unknown ({})", discord_id),
+            };
+            owners.push(Owner { discord_id, user_name });
+        }
+        Ok(owners)
+    }
+
     async fn get_discord_ids_for_rs_name(&self, rs_name: &str, db: &SqlitePool) -> Result<Vec<String>> {
         debug!("Looking up Discord IDs for RS name: {}", rs_name);
         
@@ -387,22 +625,30 @@ impl RunescapeTracker {
         
         Ok(user.name)
     }
+}
 
-
-    async fn get_rs_name_for_author(&self, author_id: &str, db: &SqlitePool) -> Result<String> {
-        let records = sqlx::query!(
-            "SELECT runescape_name FROM runescape_accounts WHERE discord_id = ? LIMIT 1",
-            author_id
-        )
-        .fetch_all(db)
-        .await?;
-        
-        Ok(records.first()
-            .and_then(|record| Some(record.runescape_name.clone()))
-            .unwrap_or_default())
+/// Edits the backfill's progress message with the counts so far
+async fn report_progress(ctx: &Context, progress: Option<&mut Message>, summary: &BackfillSummary) {
+    if let Some(progress) = progress {
+        let content = format!("Backfilling, {} so far...", summary.describe());
+        if let Err(why) = progress.edit(ctx, EditMessage::new().content(content)).await {
+            warn!("Could not update the backfill progress: {:?}", why);
+        }
     }
 }
 
+/// Whether the message could be a RuneLite notification: posted by a bot or webhook, but not by this bot,
+/// whose own replies in the channel aren't loot
+pub fn is_runelite_message(ctx: &Context, msg: &Message) -> bool {
+    msg.author.bot && msg.author.id != ctx.cache.current_user().id
+}
+
+/// The first message ID Discord could have given out at `time`, for paging through history from a date
+pub fn message_id_at(time: NaiveDateTime) -> MessageId {
+    let ms = (time.and_utc().timestamp_millis() - DISCORD_EPOCH_MS).max(0) as u64;
+    MessageId::new((ms << 22).max(1))
+}
+
 pub struct RunescapeTrackerKey;
 
 impl TypeMapKey for RunescapeTrackerKey {
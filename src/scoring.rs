@@ -0,0 +1,270 @@
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// The adjustable rules, as named in `scoring_rules`, and what they do
+pub const RULES: &[(&str, &str)] = &[
+    ("gp_per_point", "GP of drop value per point"),
+    ("rare_max_rate", "Completion % at or below which a clog is rare"),
+    ("rare_base", "Rare clog base points"),
+    ("rare_multiplier", "Rare clog rarity multiplier"),
+    ("rare_exponent", "Rare clog rarity exponent"),
+    ("uncommon_max_rate", "Completion % at or below which a clog is uncommon"),
+    ("uncommon_min_points", "Uncommon clog points at the uncommon breakpoint"),
+    ("uncommon_max_points", "Uncommon clog points at the rare breakpoint"),
+    ("common_base", "Common clog points before the completion % is taken off"),
+    ("common_slope", "Points a common clog loses per completion %"),
+    ("clamp_cap", "Most points a clog in a clamped category can give, unless the category has its own cap"),
+    ("pet_points", "Points for a new pet"),
+    ("duplicate_pet_points", "Points for a pet the member already had"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverrideKind {
+    Drop,
+    Clog,
+}
+
+impl OverrideKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverrideKind::Drop => "drop",
+            OverrideKind::Clog => "clog",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "drop" => Some(OverrideKind::Drop),
+            "clog" => Some(OverrideKind::Clog),
+            _ => None,
+        }
+    }
+}
+
+/// Every point formula in the bot. Load it from the database wherever points are awarded
+/// so a rules change applies everywhere at once.
+#[derive(Debug, Clone)]
+pub struct ScoringRules {
+    pub gp_per_point: f64,
+    pub rare_max_rate: f64,
+    pub rare_base: f64,
+    pub rare_multiplier: f64,
+    pub rare_exponent: f64,
+    pub uncommon_max_rate: f64,
+    pub uncommon_min_points: f64,
+    pub uncommon_max_points: f64,
+    pub common_base: f64,
+    pub common_slope: f64,
+    pub clamp_cap: f64,
+    pub pet_points: f64,
+    pub duplicate_pet_points: f64,
+    pub overrides: HashMap<(OverrideKind, String), i64>,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            gp_per_point: 100_000.0,
+            rare_max_rate: 5.0,
+            rare_base: 100.0,
+            rare_multiplier: 30.0,
+            rare_exponent: 1.5,
+            uncommon_max_rate: 20.0,
+            uncommon_min_points: 200.0,
+            uncommon_max_points: 500.0,
+            common_base: 100.0,
+            common_slope: 0.5,
+            clamp_cap: 3000.0,
+            pet_points: 500.0,
+            duplicate_pet_points: 0.0,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl ScoringRules {
+    pub async fn load(db: &SqlitePool) -> Result<Self> {
+        let mut rules = Self::default();
+
+        let rows = sqlx::query!("SELECT name, value FROM scoring_rules")
+            .fetch_all(db)
+            .await?;
+        for row in rows {
+            if let Some(name) = row.name {
+                if !rules.set(&name, row.value) {
+                    warn!("Ignoring unknown scoring rule {}", name);
+                }
+            }
+        }
+
+        let overrides = sqlx::query!("SELECT kind, item_name, points FROM scoring_overrides")
+            .fetch_all(db)
+            .await?;
+        for row in overrides {
+            if let Some(kind) = OverrideKind::parse(&row.kind) {
+                rules.overrides.insert((kind, row.item_name), row.points);
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Sets a rule by name, returning false if there's no such rule
+    pub fn set(&mut self, name: &str, value: f64) -> bool {
+        let field = match name {
+            "gp_per_point" => &mut self.gp_per_point,
+            "rare_max_rate" => &mut self.rare_max_rate,
+            "rare_base" => &mut self.rare_base,
+            "rare_multiplier" => &mut self.rare_multiplier,
+            "rare_exponent" => &mut self.rare_exponent,
+            "uncommon_max_rate" => &mut self.uncommon_max_rate,
+            "uncommon_min_points" => &mut self.uncommon_min_points,
+            "uncommon_max_points" => &mut self.uncommon_max_points,
+            "common_base" => &mut self.common_base,
+            "common_slope" => &mut self.common_slope,
+            "clamp_cap" => &mut self.clamp_cap,
+            "pet_points" => &mut self.pet_points,
+            "duplicate_pet_points" => &mut self.duplicate_pet_points,
+            _ => return false,
+        };
+        *field = value;
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "gp_per_point" => self.gp_per_point,
+            "rare_max_rate" => self.rare_max_rate,
+            "rare_base" => self.rare_base,
+            "rare_multiplier" => self.rare_multiplier,
+            "rare_exponent" => self.rare_exponent,
+            "uncommon_max_rate" => self.uncommon_max_rate,
+            "uncommon_min_points" => self.uncommon_min_points,
+            "uncommon_max_points" => self.uncommon_max_points,
+            "common_base" => self.common_base,
+            "common_slope" => self.common_slope,
+            "clamp_cap" => self.clamp_cap,
+            "pet_points" => self.pet_points,
+            "duplicate_pet_points" => self.duplicate_pet_points,
+            _ => return None,
+        })
+    }
+
+    /// Points for a drop of `quantity` items worth `total_value` gp altogether
+    pub fn drop_points(&self, item_name: &str, quantity: i64, total_value: i64) -> i64 {
+        if let Some(points) = self.overrides.get(&(OverrideKind::Drop, item_name.to_string())) {
+            return points * quantity.max(1);
+        }
+        (total_value as f64 / self.gp_per_point.max(1.0)).floor() as i64
+    }
+
+    /// Points for a pet, `duplicate` if the member already had it
+    pub fn pet_points(&self, duplicate: bool) -> i64 {
+        let points = if duplicate { self.duplicate_pet_points } else { self.pet_points };
+        points.round() as i64
+    }
+
+    /// Points for a collection log entry, given the item's completion rate (%) and, if it's
+    /// clamped (in a clamped category and not whitelisted), the most points it can give
+    pub fn clog_points(&self, item_name: &str, completion_rate: f64, cap: Option<f64>) -> i64 {
+        if let Some(points) = self.overrides.get(&(OverrideKind::Clog, item_name.to_string())) {
+            return *points;
+        }
+
+        // Multi-tiered point calculation
+        let points = if completion_rate <= self.rare_max_rate {
+            // Tier 3: Mega-rare items, with the default rules
+            // 5% -> 500 points
+            // 3% -> 1000 points
+            // 1% -> 15000 points
+            // 0.5% -> 30000 points
+            let rarity_multiplier = (1.0 / completion_rate).powf(self.rare_exponent) * self.rare_multiplier;
+            self.rare_base * rarity_multiplier
+        } else if completion_rate <= self.uncommon_max_rate {
+            // Tier 2: Moderately rare items, linear interpolation between the breakpoints
+            let progress = (self.uncommon_max_rate - completion_rate) / (self.uncommon_max_rate - self.rare_max_rate); // 0 to 1 scale
+            self.uncommon_min_points + (progress * (self.uncommon_max_points - self.uncommon_min_points))
+        } else {
+            // Tier 1: Common items, simple linear scaling
+            self.common_base - (completion_rate * self.common_slope)
+        };
+
+        // Caps can be set as low as 1 point per category, so every tier is capped
+        let points = match cap {
+            Some(cap) => points.min(cap),
+            None => points,
+        };
+
+        points.round() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules_with(overrides: &[(OverrideKind, &str, i64)]) -> ScoringRules {
+        let mut rules = ScoringRules::default();
+        for (kind, item_name, points) in overrides {
+            rules.overrides.insert((*kind, item_name.to_string()), *points);
+        }
+        rules
+    }
+
+    #[test]
+    fn drop_points_divide_by_gp_per_point() {
+        let rules = ScoringRules::default();
+        assert_eq!(rules.drop_points("Dragon bones", 1, 250_000), 2);
+        assert_eq!(rules.drop_points("Dragon bones", 1, 99_999), 0);
+    }
+
+    #[test]
+    fn drop_points_keep_a_fractional_gp_per_point() {
+        let mut rules = ScoringRules::default();
+        assert!(rules.set("gp_per_point", 1500.5));
+        assert_eq!(rules.drop_points("Dragon bones", 1, 1500), 0);
+        assert_eq!(rules.drop_points("Dragon bones", 1, 3001), 2);
+    }
+
+    #[test]
+    fn drop_points_never_divide_by_less_than_one() {
+        let rules = ScoringRules { gp_per_point: 0.0, ..Default::default() };
+        assert_eq!(rules.drop_points("Coins", 1, 1234), 1234);
+    }
+
+    #[test]
+    fn drop_override_is_per_item() {
+        let rules = rules_with(&[(OverrideKind::Drop, "Twisted bow", 100)]);
+        assert_eq!(rules.drop_points("Twisted bow", 2, 1), 200);
+        assert_eq!(rules.drop_points("Twisted bow", 0, 1), 100);
+        // A clog override for the same name doesn't apply to drops
+        let rules = rules_with(&[(OverrideKind::Clog, "Twisted bow", 100)]);
+        assert_eq!(rules.drop_points("Twisted bow", 1, 1_000_000_000), 10_000);
+    }
+
+    #[test]
+    fn clog_points_per_tier() {
+        let rules = ScoringRules::default();
+        assert_eq!(rules.clog_points("Rare", 1.0, None), 3000);
+        assert_eq!(rules.clog_points("Uncommon", 10.0, None), 400);
+        assert_eq!(rules.clog_points("Common", 50.0, None), 75);
+    }
+
+    #[test]
+    fn clog_cap_applies_in_every_tier() {
+        let rules = ScoringRules::default();
+        assert_eq!(rules.clog_points("Rare", 1.0, Some(50.0)), 50);
+        assert_eq!(rules.clog_points("Uncommon", 10.0, Some(50.0)), 50);
+        assert_eq!(rules.clog_points("Common", 50.0, Some(50.0)), 50);
+        assert_eq!(rules.clog_points("Common", 50.0, Some(1000.0)), 75);
+    }
+
+    #[test]
+    fn clog_override_wins_over_the_cap() {
+        let rules = rules_with(&[(OverrideKind::Clog, "Pet snakeling", 5000)]);
+        assert_eq!(rules.clog_points("Pet snakeling", 1.0, Some(50.0)), 5000);
+        assert_eq!(rules.clog_points("Pet snakeling", 90.0, None), 5000);
+    }
+}
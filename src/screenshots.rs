@@ -0,0 +1,86 @@
+use serenity::all::Attachment;
+use serenity::prelude::*;
+use std::path::Path;
+use tracing::{debug, warn};
+use crate::config::ConfigKey;
+
+/// Screenshot evidence for a drop or collection log entry
+#[derive(Debug, Clone, Default)]
+pub struct Screenshot {
+    /// The Discord attachment URL
+    pub url: Option<String>,
+    /// Where the downloaded copy was saved, if `SCREENSHOT_DIR` is set
+    pub path: Option<String>,
+}
+
+impl Screenshot {
+    pub fn new(url: Option<String>, path: Option<String>) -> Self {
+        Self { url, path }
+    }
+
+    /// Text to append to a listing or log line, empty when there's no screenshot
+    pub fn link(&self) -> String {
+        describe(self.url.as_deref(), self.path.as_deref())
+    }
+}
+
+/// Same as `Screenshot::link`, for rows read straight from the database
+pub fn describe(url: Option<&str>, path: Option<&str>) -> String {
+    let mut text = String::new();
+    if let Some(url) = url {
+        // Angle brackets stop Discord from embedding every screenshot in the message
+        text += format!(" [screenshot](<{}>)", url).as_str();
+    }
+    if let Some(path) = path {
+        text += format!(" (saved as `{}`)", path).as_str();
+    }
+    text
+}
+
+/// The screenshot a RuneLite plugin attached to its message, preferring images over other files
+pub fn message_attachment(attachments: &[Attachment]) -> Option<&Attachment> {
+    attachments
+        .iter()
+        .find(|attachment| attachment.content_type.as_deref().is_some_and(|kind| kind.starts_with("image/")))
+        .or_else(|| attachments.first())
+}
+
+/// Keeps the attachment as evidence: always its URL, plus a downloaded copy when `SCREENSHOT_DIR` is set.
+/// A failed download is only logged, the URL is still worth keeping
+pub async fn archive(ctx: &Context, attachment: &Attachment) -> Screenshot {
+    let screenshot_dir = {
+        let data = ctx.data.read().await;
+        data.get::<ConfigKey>().and_then(|config| config.screenshot_dir.clone())
+    };
+
+    let path = match screenshot_dir {
+        Some(dir) => match download(&dir, attachment).await {
+            Ok(path) => Some(path),
+            Err(why) => {
+                warn!("Failed to save screenshot {}: {:?}", attachment.url, why);
+                None
+            }
+        },
+        None => None,
+    };
+
+    Screenshot::new(Some(attachment.url.clone()), path)
+}
+
+async fn download(dir: &Path, attachment: &Attachment) -> anyhow::Result<String> {
+    let bytes = attachment.download().await?;
+
+    // Attachment IDs are unique, the file name is only kept to make the folder browsable
+    let file_name: String = attachment.filename
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let day_dir = dir.join(chrono::Utc::now().format("%Y-%m-%d").to_string());
+    tokio::fs::create_dir_all(&day_dir).await?;
+
+    let path = day_dir.join(format!("{}_{}", attachment.id, file_name));
+    tokio::fs::write(&path, bytes).await?;
+
+    debug!("Saved screenshot {} to {}", attachment.url, path.display());
+    Ok(path.display().to_string())
+}
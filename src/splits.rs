@@ -0,0 +1,679 @@
+use anyhow::{bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::all::{
+    ButtonStyle,
+    ChannelId,
+    ComponentInteraction,
+    CreateActionRow,
+    CreateButton,
+    CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+    CreateMessage,
+    EditInteractionResponse,
+    ReactionType,
+    UserId,
+};
+use serenity::prelude::*;
+use sqlx::{SqliteConnection, SqlitePool};
+use tracing::debug;
+use crate::command_handler::format_number;
+use crate::rank_manager::{self, PointsUpdate};
+use crate::logger;
+use crate::processed_messages;
+use crate::ledger::{self, PointChange, Reason};
+
+/// Teammates react with this on an auto-tracked drop to ask to join its split
+pub const JOIN_EMOJI: &str = "🤝";
+
+/// Button IDs are these prefixes followed by the join request ID, so they keep working after a restart
+const CONFIRM_PREFIX: &str = "split_join_confirm:";
+const DECLINE_PREFIX: &str = "split_join_decline:";
+
+/// How long after an auto-tracked drop teammates can still react to join it
+const JOIN_WINDOW_HOURS: i64 = 24;
+
+lazy_static! {
+    static ref MENTION_REGEX: Regex = Regex::new(r"<@!?(\d+)>").unwrap();
+    /// Re-dividing a split reads and rewrites every share, so only one runs at a time in this process
+    static ref SPLIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// One participant in a split drop
+#[derive(Debug, Clone)]
+pub struct Share {
+    pub discord_id: String,
+    pub user_name: String,
+    /// Relative size of the share, e.g. 2 and 1 for two-thirds and one-third
+    pub weight: i64,
+}
+
+/// Splits `total` by weight. The parts are rounded down and what's left over goes to the biggest
+/// remainders first, so they always add up to exactly `total`
+pub fn divide(total: i64, weights: &[i64]) -> Vec<i64> {
+    let weight_sum: i64 = weights.iter().sum();
+    if weight_sum <= 0 {
+        return vec![0; weights.len()];
+    }
+    // A negative total is split like a positive one, so the parts round towards zero the same way
+    if total < 0 {
+        return divide(-total, weights).into_iter().map(|part| -part).collect();
+    }
+
+    let mut parts: Vec<i64> = weights.iter().map(|weight| total * weight / weight_sum).collect();
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by_key(|&i| std::cmp::Reverse(total * weights[i] % weight_sum));
+
+    let left_over = total - parts.iter().sum::<i64>();
+    for &i in by_remainder.iter().take(left_over as usize) {
+        parts[i] += 1;
+    }
+    parts
+}
+
+/// The members @mentioned in a `teammates` option, in order and without repeats
+pub fn parse_teammates(text: &str) -> Vec<UserId> {
+    let mut teammates = Vec::new();
+    for capture in MENTION_REGEX.captures_iter(text) {
+        if let Ok(id) = capture[1].parse::<u64>() {
+            let user_id = UserId::new(id);
+            if !teammates.contains(&user_id) {
+                teammates.push(user_id);
+            }
+        }
+    }
+    teammates
+}
+
+/// Parses the `shares` option, one positive whole number per participant. Even shares if it's left out
+pub fn parse_weights(text: Option<&str>, participants: usize) -> Result<Vec<i64>, String> {
+    let Some(text) = text else {
+        return Ok(vec![1; participants]);
+    };
+
+    let weights = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '/' || c == ':')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<i64>().ok().filter(|weight| *weight > 0))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("'{}' isn't a list of shares, use whole numbers like `2 1 1`.", text))?;
+
+    if weights.len() != participants {
+        return Err(format!(
+            "Give {} shares, yours first and then one per teammate in the order they're mentioned.",
+            participants
+        ));
+    }
+    Ok(weights)
+}
+
+/// Stores the shares of a split submission as `discord_id:weight` pairs
+pub fn encode(shares: &[Share]) -> Option<String> {
+    if shares.is_empty() {
+        return None;
+    }
+    Some(shares
+        .iter()
+        .map(|share| format!("{}:{}", share.discord_id, share.weight))
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+/// Reads shares stored by `encode`, looking up everyone's current name
+pub async fn decode(ctx: &Context, text: &str) -> Vec<Share> {
+    let mut shares = Vec::new();
+    for pair in text.split(',') {
+        let Some((discord_id, weight)) = pair.split_once(':') else {
+            continue;
+        };
+        let Ok(weight) = weight.parse::<i64>() else {
+            continue;
+        };
+        shares.push(Share {
+            discord_id: discord_id.to_string(),
+            user_name: user_name(ctx, discord_id).await,
+            weight,
+        });
+    }
+    shares
+}
+
+/// Display name for a Discord ID, for rank up messages
+pub async fn user_name(ctx: &Context, discord_id: &str) -> String {
+    let user = match discord_id.parse::<u64>() {
+        Ok(id) => UserId::new(id).to_user(&ctx.http).await.ok(),
+        Err(_) => None,
+    };
+    user.map(|user| user.display_name().to_string())
+        .unwrap_or_else(|| format!("Unknown ({})", discord_id))
+}
+
+/// " (reached Rank)" after a participant's points, if their share ranked them up
+pub fn rank_note(points_update: &PointsUpdate) -> String {
+    if points_update.crossed_ranks.is_empty() {
+        String::new()
+    } else {
+        format!(" 🎆 reached {}", points_update.crossed_ranks.join(", "))
+    }
+}
+
+/// Asks the drop's recipients to confirm the member was there. Nothing is split until one of them, or a
+/// mod, does. Reacting again doesn't ask again
+pub async fn request_join(
+    ctx: &Context,
+    db: &SqlitePool,
+    channel_id: ChannelId,
+    message_id: &str,
+    joiner_id: &str,
+) -> Result<()> {
+    let window = format!("-{} hours", JOIN_WINDOW_HOURS);
+    let drops = sqlx::query!(
+        r#"SELECT discord_id, item_name, quantity, counted AS "counted: bool"
+           FROM drops
+           WHERE message_id = ? AND timestamp >= datetime('now', ?)
+           ORDER BY id"#,
+        message_id,
+        window
+    )
+    .fetch_all(db)
+    .await?;
+
+    // A recipient, or a teammate who already joined, can't take another share
+    if drops.iter().any(|drop| drop.discord_id == joiner_id) {
+        return Ok(());
+    }
+
+    let mut recipients: Vec<&str> = Vec::new();
+    let mut items = Vec::new();
+    for drop in drops.iter().filter(|drop| drop.counted) {
+        if !recipients.contains(&drop.discord_id.as_str()) {
+            recipients.push(&drop.discord_id);
+        }
+        let item = format!("{}x {}", format_number(drop.quantity), drop.item_name);
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let inserted = sqlx::query!(
+        "INSERT INTO split_join_requests (message_id, joiner_id) VALUES (?, ?)
+         ON CONFLICT (message_id, joiner_id) DO NOTHING",
+        message_id,
+        joiner_id
+    )
+    .execute(db)
+    .await?;
+    if inserted.rows_affected() == 0 {
+        return Ok(());
+    }
+    let request_id = inserted.last_insert_rowid();
+
+    let mentions: Vec<String> = recipients.iter().map(|discord_id| format!("<@{}>", discord_id)).collect();
+    channel_id
+        .send_message(&ctx.http, CreateMessage::new()
+            .content(format!(
+                "<@{}> wants to join the split of {}. {}, confirm they were there (or a mod can).",
+                joiner_id,
+                items.join(", "),
+                mentions.join(" ")
+            ))
+            .components(join_buttons(request_id)))
+        .await?;
+
+    debug!("{} asked to join the drops of message {} (request #{})", joiner_id, message_id, request_id);
+    Ok(())
+}
+
+fn join_buttons(request_id: i64) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}{}", CONFIRM_PREFIX, request_id))
+            .label("Confirm")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}{}", DECLINE_PREFIX, request_id))
+            .label("Decline")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Confirm/Decline button on a join request. Only one of the drop's recipients or a mod can use them
+pub async fn handle_join_review(
+    component: &ComponentInteraction,
+    ctx: &Context,
+    db: &SqlitePool,
+) -> Result<()> {
+    let custom_id = component.data.custom_id.as_str();
+    let (confirm, request_id) = match (custom_id.strip_prefix(CONFIRM_PREFIX), custom_id.strip_prefix(DECLINE_PREFIX)) {
+        (Some(id), _) => (true, id),
+        (_, Some(id)) => (false, id),
+        _ => return Ok(()),
+    };
+    let request_id: i64 = request_id.parse()?;
+    let reviewer_id = component.user.id.to_string();
+
+    let Some(request) = sqlx::query!(
+        "SELECT message_id, joiner_id, status FROM split_join_requests WHERE id = ?",
+        request_id
+    )
+    .fetch_optional(db)
+    .await? else {
+        return Ok(());
+    };
+
+    let is_mod = component.member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.administrator());
+    let is_recipient = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM drops WHERE message_id = ? AND discord_id = ? AND counted = 1) AS "recipient!: bool""#,
+        request.message_id,
+        reviewer_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    let refusal = if request.joiner_id == reviewer_id {
+        Some("You can't confirm your own join.")
+    } else if !is_recipient && !is_mod {
+        Some("Only someone who got this drop, or a mod, can confirm who was there.")
+    } else {
+        None
+    };
+    if let Some(refusal) = refusal {
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(refusal)
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    // Claiming it with the status check means two clicks at once can't both add the share
+    let status = if confirm {"confirmed"} else {"declined"};
+    let claimed = sqlx::query!(
+        "UPDATE split_join_requests SET status = ?, reviewer_id = ?, reviewed_at = CURRENT_TIMESTAMP
+         WHERE id = ? AND status = 'pending'",
+        status,
+        reviewer_id,
+        request_id
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+    if claimed == 0 {
+        component
+            .create_response(&ctx.http, CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!("This join request was already {}.", request.status))
+                    .ephemeral(true)
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let outcome = if confirm {
+        let joiner = Share {
+            discord_id: request.joiner_id.clone(),
+            user_name: user_name(ctx, &request.joiner_id).await,
+            weight: 1,
+        };
+        match join_message_drops(ctx, db, &request.message_id, &joiner).await {
+            Ok(joined) if joined.is_empty() => format!(
+                "✅ Confirmed by <@{}>, but <@{}> has nothing left to join.",
+                reviewer_id,
+                request.joiner_id
+            ),
+            Ok(joined) => format!("✅ Confirmed by <@{}>\n{}", reviewer_id, joined.join("\n")),
+            Err(why) => {
+                // Nothing was split, so it can be confirmed again once whatever failed is fixed
+                sqlx::query!(
+                    "UPDATE split_join_requests SET status = 'pending', reviewer_id = NULL, reviewed_at = NULL WHERE id = ?",
+                    request_id
+                )
+                .execute(db)
+                .await?;
+                return Err(why);
+            }
+        }
+    } else {
+        format!("❌ <@{}> joining the split was declined by <@{}>", request.joiner_id, reviewer_id)
+    };
+
+    component
+        .edit_response(&ctx.http, EditInteractionResponse::new()
+            .content(outcome)
+            .components(Vec::new()))
+        .await?;
+    Ok(())
+}
+
+/// What joining one drop changed, for the notifications once the transaction is committed
+struct Joined {
+    split_id: i64,
+    drop_id: i64,
+    item_name: String,
+    quantity: i64,
+    ways: usize,
+    points: i64,
+    /// How much each existing share's points changed, by Discord ID
+    shrunk: Vec<(String, i64)>,
+}
+
+/// Adds the member to every drop recorded from the RuneLite message, re-dividing each one evenly
+/// (or by its existing shares) between the participants. An RS name linked to several members gives
+/// each of them a row for the same item, the joiner only gets a share of the first. Every drop is
+/// joined in one transaction and the rank notifications go out after it commits. Returns a line per
+/// drop joined
+pub async fn join_message_drops(
+    ctx: &Context,
+    db: &SqlitePool,
+    message_id: &str,
+    joiner: &Share,
+) -> Result<Vec<String>> {
+    let _lock = SPLIT_LOCK.lock().await;
+    let mut tx = db.begin().await?;
+
+    let window = format!("-{} hours", JOIN_WINDOW_HOURS);
+    let drops = sqlx::query!(
+        r#"SELECT d.id AS "id!: i64", d.discord_id, d.item_name, d.quantity, d.split_id,
+                  d.counted AS "counted: bool", s.version AS "version?: i64"
+           FROM drops d
+           LEFT JOIN drop_splits s ON s.id = d.split_id
+           WHERE d.message_id = ? AND d.timestamp >= datetime('now', ?)
+           ORDER BY d.id"#,
+        message_id,
+        window
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // A recipient, or a teammate who already joined, can't take another share
+    if drops.iter().any(|drop| drop.discord_id == joiner.discord_id) {
+        return Ok(Vec::new());
+    }
+
+    let mut joined = Vec::new();
+    let mut seen_items: Vec<(&str, i64)> = Vec::new();
+    for drop in drops.iter().filter(|drop| drop.counted) {
+        let item = (drop.item_name.as_str(), drop.quantity);
+        if seen_items.contains(&item) {
+            continue;
+        }
+        seen_items.push(item);
+
+        let split = drop.split_id.zip(drop.version);
+        joined.push(join_drop(&mut tx, message_id, drop.id, split, joiner).await?);
+    }
+    if joined.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    tx.commit().await?;
+
+    // One notification per member, however many of their drops were re-divided
+    let mut shrunk: Vec<(&str, i64)> = Vec::new();
+    for (discord_id, delta) in joined.iter().flat_map(|join| &join.shrunk) {
+        match shrunk.iter_mut().find(|(member, _)| member == discord_id) {
+            Some((_, total)) => *total += delta,
+            None => shrunk.push((discord_id, *delta)),
+        }
+    }
+    for (discord_id, delta) in shrunk {
+        if delta != 0 {
+            rank_manager::points_recorded(ctx, discord_id, &user_name(ctx, discord_id).await, delta, db).await?;
+        }
+    }
+    let joiner_points = joined.iter().map(|join| join.points).sum();
+    let points_update = rank_manager::points_recorded(ctx, &joiner.discord_id, &joiner.user_name, joiner_points, db).await?;
+
+    let mut lines = Vec::new();
+    for join in &joined {
+        debug!("{} joined split #{} of {}", joiner.discord_id, join.split_id, join.item_name);
+
+        logger::log_action(
+            ctx,
+            &joiner.discord_id,
+            "JOINED SPLIT",
+            &format!(
+                "{}x {} [split #{}], now shared {} ways (+{} points) [ID: {}]",
+                join.quantity,
+                join.item_name,
+                join.split_id,
+                join.ways,
+                join.points,
+                join.drop_id
+            ),
+        ).await?;
+
+        lines.push(format!(
+            "{} is now split {} ways, <@{}> gets +{} points",
+            join.item_name,
+            join.ways,
+            joiner.discord_id,
+            format_number(join.points)
+        ));
+    }
+    if let Some(last) = lines.last_mut() {
+        last.push_str(&rank_note(&points_update));
+    }
+
+    Ok(lines)
+}
+
+/// Gives the member an even share of the drop and shrinks everyone else's to match, turning it into a
+/// split first if it isn't one yet. `split` is the drop's split and its version when the shares were read
+async fn join_drop(
+    conn: &mut SqliteConnection,
+    message_id: &str,
+    drop_id: i64,
+    split: Option<(i64, i64)>,
+    joiner: &Share,
+) -> Result<Joined> {
+    // SPLIT_LOCK only covers this process. Another one re-dividing the split since it was read bumps
+    // its version, and writing over those shares would lose its joiner
+    let split_id = match split {
+        Some((split_id, version)) => {
+            let current = sqlx::query!(
+                "UPDATE drop_splits SET version = version + 1 WHERE id = ? AND version = ?",
+                split_id,
+                version
+            )
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+            if current == 0 {
+                bail!("Split #{} was changed while joining it", split_id);
+            }
+            split_id
+        }
+        None => start_split(conn, drop_id, &joiner.discord_id).await?,
+    };
+
+    let split = sqlx::query!(
+        "SELECT item_name, quantity, total_value, total_points FROM drop_splits WHERE id = ?",
+        split_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let members = sqlx::query!(
+        r#"SELECT id AS "id!: i64", discord_id, value, points, share FROM drops WHERE split_id = ? ORDER BY id"#,
+        split_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut weights: Vec<i64> = members.iter().map(|member| member.share.unwrap_or(1)).collect();
+    weights.push(joiner.weight);
+    let values = divide(split.total_value, &weights);
+    let points = divide(split.total_points, &weights);
+
+    // Shrink the existing shares
+    let mut shrunk = Vec::new();
+    for (i, member) in members.iter().enumerate() {
+        sqlx::query!(
+            "UPDATE drops SET value = ?, points = ? WHERE id = ?",
+            values[i],
+            points[i],
+            member.id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        let delta = points[i] - member.points;
+        if delta != 0 {
+            ledger::record(
+                &mut *conn,
+                &member.discord_id,
+                &PointChange::new(delta, Reason::DropSplit)
+                    .source("drops", member.id)
+                    .actor(&joiner.discord_id),
+            ).await?;
+            shrunk.push((member.discord_id.clone(), delta));
+        }
+    }
+
+    // The new share copies everything else about the drop, including when it happened. It's the same
+    // drop, so it doesn't count towards the joiner's total_drops
+    let new_value = values[members.len()];
+    let new_points = points[members.len()];
+    let new_drop_id = sqlx::query!(
+        "INSERT INTO drops (discord_id, item_name, value, quantity, item_id, source, points, timestamp,
+                            screenshot_url, screenshot_path, split_id, share, message_id, counted)
+         SELECT ?, item_name, ?, quantity, item_id, source, ?, timestamp,
+                screenshot_url, screenshot_path, split_id, ?, message_id, 0
+         FROM drops WHERE id = ?",
+        joiner.discord_id,
+        new_value,
+        new_points,
+        joiner.weight,
+        drop_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    // The new share came from the same RuneLite message
+    processed_messages::link_row(&mut *conn, message_id, "drops", new_drop_id).await?;
+
+    ledger::record(
+        &mut *conn,
+        &joiner.discord_id,
+        &PointChange::new(new_points, Reason::Drop)
+            .source("drops", new_drop_id)
+            .actor(&joiner.discord_id),
+    ).await?;
+
+    Ok(Joined {
+        split_id,
+        drop_id: new_drop_id,
+        item_name: split.item_name,
+        quantity: split.quantity,
+        ways: weights.len(),
+        points: new_points,
+        shrunk,
+    })
+}
+
+/// Turns a single recipient's drop into a split with them as the only participant
+async fn start_split(conn: &mut SqliteConnection, drop_id: i64, actor_id: &str) -> Result<i64> {
+    let split_id = sqlx::query!(
+        "INSERT INTO drop_splits (item_name, quantity, total_value, total_points, source, actor_id)
+         SELECT item_name, quantity, value, points, source, ? FROM drops WHERE id = ?",
+        actor_id,
+        drop_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    // Another process may have split it since it was read
+    let started = sqlx::query!(
+        "UPDATE drops SET split_id = ?, share = 1 WHERE id = ? AND split_id IS NULL",
+        split_id,
+        drop_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if started == 0 {
+        bail!("Drop #{} was split while joining it", drop_id);
+    }
+
+    Ok(split_id)
+}
+
+/// The reaction teammates use to join a drop
+pub fn join_reaction() -> ReactionType {
+    ReactionType::Unicode(JOIN_EMOJI.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_parts_add_up_to_the_total() {
+        assert_eq!(divide(100, &[1, 1, 1]), vec![34, 33, 33]);
+        assert_eq!(divide(90, &[1, 1, 1]), vec![30, 30, 30]);
+        assert_eq!(divide(2, &[1, 1, 1]), vec![1, 1, 0]);
+    }
+
+    #[test]
+    fn weighted_parts_round_towards_the_biggest_remainders() {
+        assert_eq!(divide(100, &[2, 1, 1]), vec![50, 25, 25]);
+        // 10 * 2/3 = 6.67 and 10 * 1/3 = 3.33
+        assert_eq!(divide(10, &[2, 1]), vec![7, 3]);
+        assert_eq!(divide(7, &[1, 3]), vec![2, 5]);
+    }
+
+    #[test]
+    fn negative_totals_are_split_like_positive_ones() {
+        assert_eq!(divide(-100, &[1, 1, 1]), vec![-34, -33, -33]);
+        assert_eq!(divide(-10, &[2, 1]), vec![-7, -3]);
+        assert_eq!(divide(-10, &[2, 1]).iter().sum::<i64>(), -10);
+    }
+
+    #[test]
+    fn no_weight_gives_nothing() {
+        assert_eq!(divide(100, &[]), Vec::<i64>::new());
+        assert_eq!(divide(100, &[0, 0]), vec![0, 0]);
+        assert_eq!(divide(0, &[1, 2]), vec![0, 0]);
+    }
+
+    #[test]
+    fn missing_shares_are_even() {
+        assert_eq!(parse_weights(None, 3), Ok(vec![1, 1, 1]));
+    }
+
+    #[test]
+    fn shares_take_any_separator() {
+        assert_eq!(parse_weights(Some("2 1 1"), 3), Ok(vec![2, 1, 1]));
+        assert_eq!(parse_weights(Some("2,1, 1"), 3), Ok(vec![2, 1, 1]));
+        assert_eq!(parse_weights(Some("3/1"), 2), Ok(vec![3, 1]));
+        assert_eq!(parse_weights(Some("1:2"), 2), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn shares_must_be_positive_whole_numbers() {
+        assert!(parse_weights(Some("2 0"), 2).is_err());
+        assert!(parse_weights(Some("2 -1"), 2).is_err());
+        assert!(parse_weights(Some("1.5 1"), 2).is_err());
+        assert!(parse_weights(Some("half half"), 2).is_err());
+    }
+
+    #[test]
+    fn shares_must_match_the_participants() {
+        assert!(parse_weights(Some("2 1"), 3).is_err());
+        assert!(parse_weights(Some("1 1 1 1"), 3).is_err());
+        assert!(parse_weights(Some(""), 1).is_err());
+    }
+}
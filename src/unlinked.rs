@@ -0,0 +1,275 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serenity::all::Message;
+use serenity::prelude::*;
+use sqlx::{SqliteConnection, SqlitePool};
+use tracing::debug;
+use kittyscape_loot_bot::parsers::ParsedEvent;
+use crate::command_handler::{format_gp, format_number};
+use crate::config::ConfigKey;
+use crate::command_handler::clog::{self, NewClog};
+use crate::command_handler::drop::{self, NewDrop};
+use crate::command_handler::pet::{self, NewPet};
+use crate::processed_messages;
+use crate::screenshots::{self, Screenshot};
+use crate::splits;
+
+/// A RuneLite notification waiting for its RS name to be linked
+pub struct PendingEvent {
+    pub id: i64,
+    pub rs_name: String,
+    pub kind: String,
+    pub item_name: Option<String>,
+    pub quantity: i64,
+    pub value: Option<i64>,
+    pub source: Option<String>,
+    pub duplicate: bool,
+    pub screenshot_url: Option<String>,
+    pub screenshot_path: Option<String>,
+    pub message_id: Option<String>,
+    pub message_link: String,
+    pub timestamp: Option<NaiveDateTime>,
+}
+
+impl PendingEvent {
+    /// e.g. "2x Dragon bones (5.2K gp) from Vorkath", "Clog: Pet snakeling" or "Pet: Unknown pet"
+    pub fn describe(&self) -> String {
+        let item_name = self.item_name.as_deref().unwrap_or("Unknown pet");
+        match self.kind.as_str() {
+            "drop" => {
+                let mut text = format!("{}x {} ({})", format_number(self.quantity), item_name, format_gp(self.value.unwrap_or(0)));
+                if let Some(source) = &self.source {
+                    text += format!(" from {}", source).as_str();
+                }
+                text
+            }
+            "pet" if self.duplicate => format!("Pet: {} (duplicate)", item_name),
+            "pet" => format!("Pet: {}", item_name),
+            _ => format!("Clog: {}", item_name),
+        }
+    }
+
+    /// One line for a listing, with when it happened and a link to the RuneLite message
+    pub fn line(&self) -> String {
+        format!(
+            "#{}: {} - {} [message](<{}>)",
+            self.id,
+            self.describe(),
+            self.timestamp.map(|timestamp| timestamp.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+            self.message_link
+        )
+    }
+}
+
+/// Keeps a drop, clog or pet for an RS name nobody has linked, so it can be credited once someone does
+pub async fn store(ctx: &Context, conn: &mut SqliteConnection, event: &ParsedEvent, screenshot: &Screenshot, msg: &Message) -> Result<()> {
+    let (kind, item_name, item_id, quantity, value, source, duplicate) = match event {
+        ParsedEvent::Drop(drop) => ("drop", Some(drop.item_name.as_str()), drop.item_id, drop.quantity, Some(drop.value), drop.source.as_deref(), false),
+        ParsedEvent::Clog(clog) => ("clog", Some(clog.item_name.as_str()), None, 1, None, None, false),
+        ParsedEvent::Pet(pet) => ("pet", pet.pet_name.as_deref(), None, 1, None, None, pet.duplicate),
+        _ => return Ok(()),
+    };
+    let rs_name = event.rs_name();
+    let message_id = msg.id.to_string();
+    let message_link = message_link(ctx, msg).await;
+    let timestamp = msg.timestamp.naive_utc();
+
+    let event_id = sqlx::query!(
+        "INSERT INTO unlinked_events (rs_name, kind, item_name, item_id, quantity, value, source, duplicate,
+                                      screenshot_url, screenshot_path, message_id, message_link, timestamp)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rs_name,
+        kind,
+        item_name,
+        item_id,
+        quantity,
+        value,
+        source,
+        duplicate,
+        screenshot.url,
+        screenshot.path,
+        message_id,
+        message_link,
+        timestamp
+    )
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    processed_messages::link_row(&mut *conn, &message_id, "unlinked_events", event_id).await?;
+
+    debug!("Stored unlinked {} for RS name '{}' until it's linked", kind, rs_name);
+    Ok(())
+}
+
+/// Messages fetched over HTTP, as backfilled ones are, don't say which guild they're in, so those fall
+/// back to the configured one. Without either the link would point at DMs
+async fn message_link(ctx: &Context, msg: &Message) -> String {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => Some(guild_id),
+        None => ctx.data.read().await.get::<ConfigKey>().and_then(|config| config.guild_id),
+    };
+    msg.id.link(msg.channel_id, guild_id)
+}
+
+/// Everything still waiting, oldest first, optionally only for one RS name
+pub async fn pending(db: &SqlitePool, rs_name: Option<&str>) -> Result<Vec<PendingEvent>> {
+    let rows = sqlx::query!(
+        r#"SELECT id AS "id!: i64", rs_name, kind, item_name, quantity, value, source, duplicate AS "duplicate!: bool",
+                  screenshot_url, screenshot_path, message_id, message_link, timestamp
+           FROM unlinked_events
+           WHERE status = 'pending' AND (?1 IS NULL OR rs_name = ?1 COLLATE NOCASE)
+           ORDER BY id"#,
+        rs_name
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| PendingEvent {
+            id: row.id,
+            rs_name: row.rs_name,
+            kind: row.kind,
+            item_name: row.item_name,
+            quantity: row.quantity,
+            value: row.value,
+            source: row.source,
+            duplicate: row.duplicate,
+            screenshot_url: row.screenshot_url,
+            screenshot_path: row.screenshot_path,
+            message_id: row.message_id,
+            message_link: row.message_link,
+            timestamp: row.timestamp,
+        })
+        .collect())
+}
+
+/// Records every pending event for the RS name against the member who just linked it.
+/// Returns a line per event for the reply
+pub async fn credit(
+    ctx: &Context,
+    db: &SqlitePool,
+    discord_id: &str,
+    user_name: &str,
+    rs_name: &str,
+) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for event in pending(db, Some(rs_name)).await? {
+        // Claiming it first means two members linking the same name at once can't both be credited
+        let claimed = sqlx::query!(
+            "UPDATE unlinked_events SET status = 'credited', resolved_by = ?, resolved_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'pending'",
+            discord_id,
+            event.id
+        )
+        .execute(db)
+        .await?
+        .rows_affected();
+        if claimed == 0 {
+            continue;
+        }
+
+        match credit_event(ctx, db, discord_id, user_name, &event).await {
+            Ok(Some(line)) => lines.push(line),
+            Ok(None) => {
+                sqlx::query!("UPDATE unlinked_events SET status = 'dismissed' WHERE id = ?", event.id)
+                    .execute(db)
+                    .await?;
+                lines.push(format!("• {}: already logged or no longer in the collection log, skipped", event.describe()));
+            }
+            Err(why) => {
+                // Put it back so it can be credited again once whatever failed is fixed
+                sqlx::query!(
+                    "UPDATE unlinked_events SET status = 'pending', resolved_by = NULL, resolved_at = NULL WHERE id = ?",
+                    event.id
+                )
+                .execute(db)
+                .await?;
+                return Err(why);
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// Records one event, or returns `None` for a clog the member already has or that's no longer in the collection log
+async fn credit_event(
+    ctx: &Context,
+    db: &SqlitePool,
+    discord_id: &str,
+    user_name: &str,
+    event: &PendingEvent,
+) -> Result<Option<String>> {
+    let screenshot = Screenshot::new(event.screenshot_url.clone(), event.screenshot_path.clone());
+    let item_name = event.item_name.as_deref();
+
+    let (points, points_update) = match (event.kind.as_str(), item_name) {
+        ("drop", Some(item_name)) => {
+            drop::record_drop(ctx, db, &NewDrop {
+                discord_id,
+                user_name,
+                item_name,
+                quantity: event.quantity,
+                total_value: event.value.unwrap_or(0),
+                source: event.source.as_deref(),
+                when: event.timestamp,
+                screenshot: &screenshot,
+                actor_id: discord_id,
+            }).await?
+        }
+        ("clog", Some(item_name)) => {
+            if clog::logged_at(db, discord_id, item_name).await.is_some() {
+                return Ok(None);
+            }
+            let Some(recorded) = clog::record_clog(ctx, db, &NewClog {
+                discord_id,
+                user_name,
+                item_name,
+                screenshot: &screenshot,
+                actor_id: discord_id,
+                when: event.timestamp,
+            }).await? else {
+                return Ok(None);
+            };
+            recorded
+        }
+        ("pet", _) => {
+            let duplicate = event.duplicate || match item_name {
+                Some(pet_name) => pet::pet_logged_at(db, discord_id, pet_name).await.is_some(),
+                None => false,
+            };
+            pet::record_pet(ctx, db, &NewPet {
+                discord_id,
+                user_name,
+                pet_name: item_name,
+                duplicate,
+                screenshot: &screenshot,
+                actor_id: Some(discord_id),
+                message_id: event.message_id.as_deref(),
+                when: event.timestamp,
+            }).await?
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(format!(
+        "• {}: +{} points{}{}",
+        event.describe(),
+        format_number(points),
+        screenshots::describe(event.screenshot_url.as_deref(), None),
+        splits::rank_note(&points_update)
+    )))
+}
+
+/// Marks everything pending for the RS name as dismissed. Returns how many there were
+pub async fn dismiss(db: &SqlitePool, rs_name: &str, actor_id: &str) -> Result<u64> {
+    Ok(sqlx::query!(
+        "UPDATE unlinked_events SET status = 'dismissed', resolved_by = ?, resolved_at = CURRENT_TIMESTAMP
+         WHERE status = 'pending' AND rs_name = ? COLLATE NOCASE",
+        actor_id,
+        rs_name
+    )
+    .execute(db)
+    .await?
+    .rows_affected())
+}
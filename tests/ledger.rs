@@ -0,0 +1,95 @@
+//! Checks the `point_transactions` triggers against a fresh in-memory database with every
+//! migration applied: points only move through the ledger, and the ledger always adds up to
+//! `users.points`.
+
+use kittyscape_loot_bot::ledger::{self, PointChange, Reason};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+
+async fn database() -> SqlitePool {
+    // Every connection to :memory: is its own database, so the pool keeps just the one
+    let db = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    sqlx::migrate!().run(&db).await.expect("migrations");
+    db
+}
+
+async fn points(db: &SqlitePool, discord_id: &str) -> i64 {
+    sqlx::query_scalar("SELECT points FROM users WHERE discord_id = ?")
+        .bind(discord_id)
+        .fetch_one(db)
+        .await
+        .expect("user row")
+}
+
+async fn ledger_total(db: &SqlitePool, discord_id: &str) -> i64 {
+    sqlx::query_scalar("SELECT COALESCE(SUM(delta), 0) FROM point_transactions WHERE discord_id = ?")
+        .bind(discord_id)
+        .fetch_one(db)
+        .await
+        .expect("ledger total")
+}
+
+#[tokio::test]
+async fn recorded_changes_add_up_to_the_users_points() {
+    let db = database().await;
+
+    ledger::record(&db, "1", &PointChange::new(120, Reason::Drop).source("drops", 1)).await.unwrap();
+    ledger::record(&db, "1", &PointChange::new(300, Reason::Clog).source("collection_log_entries", 1)).await.unwrap();
+    ledger::record(&db, "1", &PointChange::new(-120, Reason::DropRemoved).source("drops", 1).actor("2")).await.unwrap();
+    ledger::record(&db, "2", &PointChange::new(5, Reason::Adjustment)).await.unwrap();
+
+    assert_eq!(points(&db, "1").await, 300);
+    assert_eq!(ledger_total(&db, "1").await, 300);
+    assert_eq!(points(&db, "2").await, 5);
+    assert_eq!(ledger_total(&db, "2").await, 5);
+}
+
+#[tokio::test]
+async fn updating_points_directly_is_refused() {
+    let db = database().await;
+    ledger::record(&db, "1", &PointChange::new(100, Reason::Drop)).await.unwrap();
+
+    let result = sqlx::query("UPDATE users SET points = points + 50 WHERE discord_id = '1'")
+        .execute(&db)
+        .await;
+    let error = result.expect_err("a direct points update should abort").to_string();
+    assert!(error.contains("users.points must match point_transactions"), "{}", error);
+    assert_eq!(points(&db, "1").await, 100);
+
+    // Other columns can still change
+    sqlx::query("UPDATE users SET total_drops = 3 WHERE discord_id = '1'")
+        .execute(&db)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn the_ledger_is_append_only() {
+    let db = database().await;
+    ledger::record(&db, "1", &PointChange::new(100, Reason::Drop)).await.unwrap();
+
+    let updated = sqlx::query("UPDATE point_transactions SET delta = 1000").execute(&db).await;
+    assert!(updated.is_err());
+    let deleted = sqlx::query("DELETE FROM point_transactions").execute(&db).await;
+    assert!(deleted.is_err());
+
+    assert_eq!(ledger_total(&db, "1").await, 100);
+    assert_eq!(points(&db, "1").await, 100);
+}
+
+#[tokio::test]
+async fn a_rolled_back_change_leaves_no_points() {
+    let db = database().await;
+    ledger::record(&db, "1", &PointChange::new(100, Reason::Drop)).await.unwrap();
+
+    let mut tx = db.begin().await.unwrap();
+    ledger::record(&mut *tx, "1", &PointChange::new(50, Reason::Pet)).await.unwrap();
+    tx.rollback().await.unwrap();
+
+    assert_eq!(points(&db, "1").await, 100);
+    assert_eq!(ledger_total(&db, "1").await, 100);
+}
@@ -0,0 +1,172 @@
+//! Replays the captured RuneLite plugin messages in `tests/fixtures/runelite` through the
+//! same parser set the tracker uses and checks the events against each fixture's expectations.
+//!
+//! To add a case, drop a JSON file in the fixtures directory with the message content and
+//! embeds as Discord sent them, plus the parser and events it should produce. Attachments carry
+//! their `body`, which the test serves locally for the parsers to download.
+
+use kittyscape_loot_bot::parsers::{ParsedEvent, ParserRegistry};
+use serde::Deserialize;
+use serenity::all::{Attachment, Embed, Message};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(Deserialize)]
+struct Fixture {
+    description: String,
+    message: FixtureMessage,
+    expected: Expected,
+}
+
+#[derive(Deserialize)]
+struct FixtureMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    embeds: Vec<Embed>,
+    #[serde(default)]
+    attachments: Vec<FixtureAttachment>,
+}
+
+#[derive(Deserialize)]
+struct FixtureAttachment {
+    filename: String,
+    content_type: Option<String>,
+    /// The size Discord reports, the body's length if left out
+    size: Option<u32>,
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct Expected {
+    parser: Option<String>,
+    #[serde(default)]
+    events: Vec<ParsedEvent>,
+}
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/runelite")
+}
+
+fn load_fixtures() -> Vec<(String, Fixture)> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(fixture_dir())
+        .expect("Fixture directory missing")
+        .map(|entry| entry.expect("Unreadable fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let text = std::fs::read_to_string(&path).expect("Unreadable fixture");
+            let fixture = serde_json::from_str(&text)
+                .unwrap_or_else(|why| panic!("Invalid fixture {}: {}", name, why));
+            (name, fixture)
+        })
+        .collect()
+}
+
+/// Serves every fixture's attachment bodies over HTTP at `/<fixture>/<index>` and returns the base URL
+async fn serve_attachments(fixtures: &[(String, Fixture)]) -> String {
+    let mut bodies = HashMap::new();
+    for (name, fixture) in fixtures {
+        for (i, attachment) in fixture.message.attachments.iter().enumerate() {
+            bodies.insert(format!("/{}/{}", name, i), attachment.body.clone());
+        }
+    }
+    let bodies = Arc::new(bodies);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Can't listen for attachment downloads");
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let bodies = Arc::clone(&bodies);
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match socket.read(&mut buffer).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let response = match bodies.get(path) {
+                    Some(body) => format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    base_url
+}
+
+fn to_message(name: &str, fixture: &FixtureMessage, attachments_url: &str) -> Message {
+    let mut msg = Message::default();
+    msg.content = fixture.content.clone();
+    msg.embeds = fixture.embeds.clone();
+    msg.attachments = fixture.attachments
+        .iter()
+        .enumerate()
+        .map(|(i, attachment)| {
+            let url = format!("{}/{}/{}", attachments_url, name, i);
+            serde_json::from_value::<Attachment>(serde_json::json!({
+                "id": (i + 1).to_string(),
+                "filename": attachment.filename,
+                "content_type": attachment.content_type,
+                "size": attachment.size.unwrap_or(attachment.body.len() as u32),
+                "url": url,
+                "proxy_url": url,
+            }))
+            .expect("Invalid fixture attachment")
+        })
+        .collect();
+    msg
+}
+
+#[tokio::test]
+async fn fixtures_parse_as_expected() {
+    let registry = ParserRegistry::default();
+    let fixtures = load_fixtures();
+    assert!(!fixtures.is_empty(), "No fixtures found in {}", fixture_dir().display());
+    let attachments_url = serve_attachments(&fixtures).await;
+
+    let mut failures = Vec::new();
+    for (name, fixture) in &fixtures {
+        let msg = to_message(name, &fixture.message, &attachments_url);
+        let (parser, events) = match registry.parse(&msg).await {
+            Some((parser, events)) => (Some(parser.to_string()), events),
+            None => (None, Vec::new()),
+        };
+
+        if parser != fixture.expected.parser || events != fixture.expected.events {
+            failures.push(format!(
+                "{} ({})\n  expected {:?} -> {:?}\n  got      {:?} -> {:?}",
+                name, fixture.description, fixture.expected.parser, fixture.expected.events, parser, events
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} fixture(s) failed:\n{}", failures.len(), failures.join("\n"));
+}
+
+#[tokio::test]
+async fn every_parser_is_covered_by_a_fixture() {
+    let registry = ParserRegistry::default();
+    let fixtures = load_fixtures();
+
+    for parser in registry.parsers() {
+        assert!(
+            fixtures.iter().any(|(_, fixture)| fixture.expected.parser.as_deref() == Some(parser.name())),
+            "No fixture exercises the {} parser",
+            parser.name()
+        );
+    }
+}